@@ -4,116 +4,141 @@
 #![deny(missing_docs)]
 extern crate proc_macro;
 
-use std::collections::HashMap;
-
-use mediatype::MediaTypeBuf;
+use mediatype::{MediaTypeBuf, ReadParams};
 use proc_macro::TokenStream;
-use quote::quote;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
 use syn::{
-    Attribute, Data, DeriveInput, Fields, GenericParam, Ident, Lit, Meta, TypeParam,
-    TypeParamBound, parse_macro_input,
+    Attribute, Data, DataEnum, DeriveInput, Expr, ExprLit, ExprUnary, Fields, GenericParam,
+    Generics, Ident, Lit, LitStr, Meta, TypeParam, TypeParamBound, UnOp, parse_macro_input,
+    parse_quote, punctuated::Punctuated,
 };
 
 /// This is the proc macro for `AcceptExtractor`.
-///
-/// # Panics
-///
-/// If it fails to parse the attributes.
-#[allow(clippy::too_many_lines)]
 #[proc_macro_derive(AcceptExtractor, attributes(accept))]
 pub fn derive_accept_extractor(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    accept_extractor(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
+#[allow(clippy::too_many_lines)]
+fn accept_extractor(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let name = &input.ident;
 
-    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
-
-    let mut generics = input.generics.clone();
-
-    // we need to add <S: Send + Sync> to the impl generics for FromRequestParts
-    let s_param = GenericParam::Type(TypeParam {
-        attrs: vec![],
-        ident: Ident::new("S", proc_macro2::Span::call_site()),
-        colon_token: Some(syn::token::Colon::default()),
-        bounds: {
-            let mut bounds = syn::punctuated::Punctuated::new();
-            bounds.push(TypeParamBound::Trait(syn::TraitBound {
-                paren_token: None,
-                modifier: syn::TraitBoundModifier::None,
-                lifetimes: None,
-                path: syn::parse_str("Send").expect("Failed to parse 'Send'"),
-            }));
-            bounds.push(TypeParamBound::Trait(syn::TraitBound {
-                paren_token: None,
-                modifier: syn::TraitBoundModifier::None,
-                lifetimes: None,
-                path: syn::parse_str("Sync").expect("Failed to parse 'Sync'"),
-            }));
-            bounds
-        },
-        eq_token: None,
-        default: None,
-    });
-    generics.params.push(s_param);
+    let (type_impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
+    let generics = with_state_param(&input.generics);
     let (impl_generics, _, _) = generics.split_for_impl();
 
     let Data::Enum(data) = &input.data else {
-        panic!("AcceptExtractor can only be derived for enums");
+        return Err(syn::Error::new_spanned(
+            name,
+            "AcceptExtractor can only be derived for enums",
+        ));
     };
 
-    let has_default = data.variants.iter().any(|variant| {
-        variant.attrs.iter().any(|attr| match &attr.meta {
-            Meta::Path(path) => path.is_ident("default"),
-            _ => false,
-        })
-    });
+    let has_default = has_default_variant(data);
+
+    let args = Args::parse(
+        &input.attrs,
+        "accept",
+        &[
+            "crate",
+            "rejection",
+            "on_unacceptable",
+            "lenient",
+            "on_missing",
+            "on_any",
+        ],
+    )?;
+    let krate = args.crate_path()?;
+    let axum = quote!(#krate::__private::axum);
+
+    // The rejection type, which has to implement From<AcceptRejection>
+    let rejection = args
+        .value("rejection")
+        .map_or_else(|| quote!(#krate::AcceptRejection), |ty| quote!(#ty));
+
+    // What to respond with if no variant is acceptable
+    let on_unacceptable = args.string("on_unacceptable")?;
+    // Whether to skip invalid entries of the Accept header instead of
+    // rejecting the request
+    let lenient = args.flag("lenient");
 
-    // Match arms with ty, subty and suffix
+    // Match arms with ty, subty and suffix, returning the index of the variant
     let mut match_arms = Vec::new();
-    // Match arms with ty only (for checking mediatypes like text/*)
-    let mut match_arms_tys = HashMap::new();
+    // The variants with their main types and priorities, by index
+    let mut variants: Vec<(&Ident, String, i32)> = Vec::new();
+    // Match arms returning the media type of the variant
+    let mut content_type_arms = Vec::new();
+    // The media types of all variants, listed in 406 responses
+    let mut offered = Vec::new();
     // Store first variant to fall back to if we don't have a default.
     let mut first_variant_name = None;
 
+    // The essences of the media types with their variants, to find duplicates
+    let mut essences: Vec<(String, &Ident)> = Vec::new();
+
     for variant in &data.variants {
         let variant_name = &variant.ident;
-        let mediatype_raw = get_accept_mediatype(&variant.attrs);
-        let mediatype = MediaTypeBuf::from_string(mediatype_raw.clone()) // compile time so clone is fine
-            .expect("Failed to parse mediatype");
-        let (ty, subty, suffix) = (
-            mediatype.ty().as_str(),
-            mediatype.subty().as_str(),
-            mediatype.suffix().map(|s| s.as_str()),
-        );
+        let variant_args = Args::parse(&variant.attrs, "accept", &["mediatype", "priority"])?;
+        let (mediatype_lit, mediatype) = variant_args.mediatype(variant_name)?;
+        let (ty, subty, suffix) = lowercase_parts(&mediatype);
+        let (ty, subty, suffix) = (ty.as_str(), subty.as_str(), suffix.as_deref());
 
-        assert!(ty != "*" && subty != "*", "Please use a concrete mediatype");
+        // parameters are not matched, so variants that only differ in them
+        // would be unreachable
+        let essence = mediatype.essence().to_string().to_ascii_lowercase();
+        if let Some((_, other)) = essences.iter().find(|(e, _)| *e == essence) {
+            return Err(syn::Error::new_spanned(
+                mediatype_lit,
+                format!("Duplicate media type {essence}, already used by {other}"),
+            ));
+        }
+        essences.push((essence, variant_name));
 
         if first_variant_name.is_none() {
             first_variant_name = Some(variant_name.clone());
         }
 
-        match_arms_tys.insert(ty.to_string(), variant_name);
+        let index = variants.len();
+        let priority = variant_args.int("priority")?.unwrap_or_default();
+        variants.push((variant_name, ty.to_string(), priority));
 
-        match &variant.fields {
-            Fields::Unit => {
-                // quote encodes None to empty string, so we need to take extra
-                // steps
-                if let Some(suffix) = suffix {
-                    match_arms.push(quote! {
-                        (#ty, #subty, Some(#suffix)) => return Ok(#name::#variant_name),
-                    });
-                } else {
-                    match_arms.push(quote! {
-                        (#ty, #subty, None) => return Ok(#name::#variant_name),
-                    });
-                }
-            }
-            _ => panic!("Only unit fields are supported"),
+        let content_type = mediatype.to_string();
+        offered.push(content_type.clone());
+        content_type_arms.push(quote! {
+            #name::#variant_name => #axum::http::HeaderValue::from_static(#content_type),
+        });
+
+        check_unit(variant)?;
+
+        // quote encodes None to empty string, so we need to take extra steps
+        if let Some(suffix) = suffix {
+            match_arms.push(quote! {
+                (#ty, #subty, Some(#suffix)) => &[#index],
+            });
+        } else {
+            match_arms.push(quote! {
+                (#ty, #subty, None) => &[#index],
+            });
         }
     }
 
-    let check_and_return_default = if has_default {
+    // The variants for a missing Accept header and */*, which default to the
+    // #[default] variant
+    let on_missing = args.variant("on_missing", data)?;
+    let on_any = args.variant("on_any", data)?;
+
+    let check_and_return_default = if let Some(on_missing) = on_missing {
+        Some(quote! {
+            if mediatypes.is_empty() {
+                return Ok(#name::#on_missing);
+            }
+        })
+    } else if has_default {
         Some(quote! {
             if mediatypes.is_empty() {
                 return Ok(#name::default());
@@ -123,79 +148,835 @@ pub fn derive_accept_extractor(input: TokenStream) -> TokenStream {
         None
     };
 
-    let handle_star_star = if has_default {
-        quote! {
-            return Ok(#name::default());
+    // The indices of the variants by descending priority, the first one on
+    // ties
+    let mut by_priority: Vec<usize> = (0..variants.len()).collect();
+    by_priority.sort_by_key(|&index| std::cmp::Reverse(variants[index].2));
+    // ty/* gets the variants of that type by priority
+    let mut wildcard_arms = Vec::new();
+    for (index, (_, ty, _)) in variants.iter().enumerate() {
+        if variants[..index].iter().any(|(_, t, _)| t == ty) {
+            continue;
         }
-    } else {
-        quote! {
-            return Ok(#name::#first_variant_name);
-        }
-    };
+        let candidates = by_priority.iter().filter(|&&i| variants[i].1 == *ty);
+        wildcard_arms.push(quote! { #ty => &[#(#candidates),*], });
+    }
+    // */* gets on_any or the default first, then the variants by priority
+    let any_variant = on_any
+        .as_ref()
+        .or_else(|| default_variant(data))
+        .and_then(|any| variants.iter().position(|(v, _, _)| *v == any));
+    let any_candidates = any_variant.into_iter().chain(
+        by_priority
+            .iter()
+            .copied()
+            .filter(|&i| Some(i) != any_variant),
+    );
+    let priorities = variants.iter().map(|(_, _, priority)| priority);
+    let variant_names = variants.iter().map(|(variant_name, _, _)| variant_name);
+    let count = variants.len();
 
-    let match_arms_tys = match_arms_tys.iter().map(|(ty, variant_name)| {
-        quote! {
-            (#ty) => return Ok(#name::#variant_name),
+    // Either reject with 406 or ignore the Accept header and respond with a
+    // fallback variant, as RFC 9110 allows
+    let fallback = match on_unacceptable.as_ref().map(|lit| (lit, lit.value())) {
+        None => None,
+        Some((_, policy)) if policy == "reject" => None,
+        Some((lit, policy)) if policy == "default" => {
+            if !has_default {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    r#"#[accept(on_unacceptable = "default")] requires a #[default] variant"#,
+                ));
+            }
+            Some(quote! { #name::default() })
         }
-    });
+        Some((_, policy)) if policy == "first" => Some(quote! { #name::#first_variant_name }),
+        Some((lit, policy)) => {
+            return Err(syn::Error::new_spanned(
+                lit,
+                format!(r#"Unknown policy "{policy}", expected "default", "first" or "reject""#),
+            ));
+        }
+    };
+    let (parse_mediatypes, handle_unacceptable) = if let Some(fallback) = fallback {
+        (
+            if lenient {
+                quote! { #krate::parse_mediatypes_lenient(&parts.headers) }
+            } else {
+                quote! {
+                    match #krate::parse_mediatypes(&parts.headers) {
+                        Ok(mediatypes) => mediatypes,
+                        Err(_) => return Ok(#fallback),
+                    }
+                }
+            },
+            quote! { Ok(#fallback) },
+        )
+    } else {
+        (
+            if lenient {
+                quote! { #krate::parse_mediatypes_lenient(&parts.headers) }
+            } else {
+                quote! { #krate::parse_mediatypes(&parts.headers)? }
+            },
+            quote! {
+                const OFFERED: &[&str] = &[#(#offered),*];
+                Err(#krate::AcceptRejection::not_acceptable(&parts.headers, OFFERED).into())
+            },
+        )
+    };
 
     let expanded = quote! {
-        impl #impl_generics axum::extract::FromRequestParts<S> for #name #ty_generics #where_clause {
-            type Rejection = axum_accept::AcceptRejection;
+        impl #impl_generics #axum::extract::FromRequestParts<S> for #name #ty_generics #where_clause {
+            type Rejection = #rejection;
 
-            async fn from_request_parts(parts: &mut axum::http::request::Parts, _state: &S) -> Result<Self, Self::Rejection> {
-                let mediatypes = axum_accept::parse_mediatypes(&parts.headers)?;
+            async fn from_request_parts(parts: &mut #axum::http::request::Parts, _state: &S) -> Result<Self, Self::Rejection> {
+                #krate::record_vary(&parts.extensions, <Self as #krate::Dimension>::HEADER);
+                let mediatypes = #parse_mediatypes;
                 #check_and_return_default
-                for mt in mediatypes {
-                    match (mt.ty.as_str(), mt.subty.as_str()) {
-                        ("*", "*") => {
-                            // return either the the default or the first
-                            // variant
-                            #handle_star_star
-                        },
+                // the variants the client rejected with q=0, which are also
+                // skipped for wildcards
+                let mut rejected = [false; #count];
+                for mt in mediatypes.iter().filter(|mt| #krate::quality(mt) == 0) {
+                    // media types are case-insensitive, the variants are
+                    // lowercase
+                    let ty = mt.ty.as_str().to_ascii_lowercase();
+                    let subty = mt.subty.as_str().to_ascii_lowercase();
+                    let suffix = mt.suffix.map(|s| s.as_str().to_ascii_lowercase());
+                    let matched: &[usize] = match (ty.as_str(), subty.as_str(), suffix.as_deref()) {
+                        #(#match_arms)*
+                        _ => &[],
+                    };
+                    for &index in matched {
+                        rejected[index] = true;
+                    }
+                }
+                const PRIORITIES: [i32; #count] = [#(#priorities),*];
+                // the best match so far with its q, specificity and priority
+                let mut best: Option<(u16, u8, i32, usize)> = None;
+                for mt in &mediatypes {
+                    let q = #krate::quality(mt);
+                    // only ranges with the same q compete, and they are
+                    // sorted by q. q=0 means not acceptable.
+                    if q == 0 || best.is_some_and(|(best_q, ..)| q < best_q) {
+                        break;
+                    }
+                    let ty = mt.ty.as_str().to_ascii_lowercase();
+                    let subty = mt.subty.as_str().to_ascii_lowercase();
+                    let suffix = mt.suffix.map(|s| s.as_str().to_ascii_lowercase());
+                    // the candidates for the range and how specific it is
+                    let (specificity, candidates): (u8, &[usize]) = match (ty.as_str(), subty.as_str()) {
+                        ("*", "*") => (0, &[#(#any_candidates),*]),
                         // do we have any mediatype that shares the main type?
                         // e.g. we offer text/plain and get accept: text/*
-                        (_, "*") => match (mt.ty.as_str()) {
-                            #(#match_arms_tys)*
-                            _ => {} // continue searching
-                        },
+                        (_, "*") => (1, match ty.as_str() {
+                            #(#wildcard_arms)*
+                            _ => &[],
+                        }),
                         // do proper matching
-                        _ =>  match (mt.ty.as_str(), mt.subty.as_str(), mt.suffix.map(|s| s.as_str())) {
+                        _ => (2, match (ty.as_str(), subty.as_str(), suffix.as_deref()) {
                             #(#match_arms)*
-                            _ => {} // continue searching
-                        },
+                            _ => &[],
+                        }),
+                    };
+                    // on ties, the more specific range wins, then the variant
+                    // with the higher priority, then the earlier range
+                    if let Some(&index) = candidates.iter().find(|&&index| !rejected[index]) {
+                        let priority = PRIORITIES[index];
+                        if best.is_none_or(|(_, s, p, _)| (specificity, priority) > (s, p)) {
+                            best = Some((q, specificity, priority, index));
+                        }
                     }
                 }
+                if let Some(variant) = best.and_then(|(.., index)| [#(#name::#variant_names),*].into_iter().nth(index)) {
+                    return Ok(variant);
+                }
+
+                #handle_unacceptable
+            }
+        }
+
+        impl #type_impl_generics #krate::Dimension for #name #ty_generics #where_clause {
+            const HEADER: #axum::http::HeaderName = #axum::http::header::ACCEPT;
+        }
+
+        impl #type_impl_generics #name #ty_generics #where_clause {
+            /// The media type of the variant, including the suffix and
+            /// parameters, for the `Content-Type` header.
+            #[must_use]
+            pub fn content_type(&self) -> #axum::http::HeaderValue {
+                match self {
+                    #(#content_type_arms)*
+                }
+            }
+        }
+
+        impl #type_impl_generics #krate::Representation for #name #ty_generics #where_clause {
+            fn content_type(&self) -> #axum::http::HeaderValue {
+                Self::content_type(self)
+            }
+        }
+
+        impl #type_impl_generics #axum::response::IntoResponseParts for #name #ty_generics #where_clause {
+            type Error = ::core::convert::Infallible;
+
+            fn into_response_parts(self, mut res: #axum::response::ResponseParts) -> Result<#axum::response::ResponseParts, Self::Error> {
+                res.headers_mut().insert(#axum::http::header::CONTENT_TYPE, self.content_type());
+                Ok(res)
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// This is the proc macro for `ContentTypeExtractor`.
+#[proc_macro_derive(ContentTypeExtractor, attributes(content_type))]
+pub fn derive_content_type_extractor(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    content_type_extractor(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn content_type_extractor(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let (type_impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = with_state_param(&input.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "ContentTypeExtractor can only be derived for enums",
+        ));
+    };
+
+    let args = Args::parse(&input.attrs, "content_type", &["crate"])?;
+    let krate = args.crate_path()?;
+    let axum = quote!(#krate::__private::axum);
+
+    let has_default = has_default_variant(data);
+
+    // Match arms with ty, subty, suffix and a guard for the parameters
+    let mut match_arms = Vec::new();
+    // The supported media types, used to build the Accept-Post/Accept-Patch
+    // header of the 415 response
+    let mut supported = Vec::new();
+
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let variant_args = Args::parse(&variant.attrs, "content_type", &["mediatype"])?;
+        let (mediatype_lit, mediatype) = variant_args.mediatype(variant_name)?;
+        let (ty, subty, suffix) = lowercase_parts(&mediatype);
+
+        let params = mediatype.params().map(|(name, value)| {
+            let (name, value) = (name.as_str(), value.unquoted_str());
+            quote! { (#name, #value) }
+        });
+
+        check_unit(variant)?;
+
+        // quote encodes None to empty string, so we need to take extra steps
+        let suffix = if let Some(suffix) = suffix {
+            quote! { Some(#suffix) }
+        } else {
+            quote! { None }
+        };
+        match_arms.push(quote! {
+            (#ty, #subty, #suffix) if #krate::params_match(&[#(#params),*], &mt) => {
+                return Ok(#name::#variant_name);
+            }
+        });
+        supported.push(mediatype_lit.value());
+    }
+
+    let handle_missing = if has_default {
+        quote! {
+            return Ok(#name::default());
+        }
+    } else {
+        quote! {
+            return Err(#krate::ContentTypeRejection::UnsupportedMediaType(
+                #krate::supported_media_types_header(&parts.method),
+                SUPPORTED,
+            ));
+        }
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #axum::extract::FromRequestParts<S> for #name #ty_generics #where_clause {
+            type Rejection = #krate::ContentTypeRejection;
+
+            async fn from_request_parts(parts: &mut #axum::http::request::Parts, _state: &S) -> Result<Self, Self::Rejection> {
+                const SUPPORTED: &[&str] = &[#(#supported),*];
+
+                #krate::record_vary(&parts.extensions, <Self as #krate::Dimension>::HEADER);
+
+                let Some(mt) = #krate::parse_content_type(&parts.headers)? else {
+                    #handle_missing
+                };
+                // media types are case-insensitive, the variants are lowercase
+                let ty = mt.ty.as_str().to_ascii_lowercase();
+                let subty = mt.subty.as_str().to_ascii_lowercase();
+                let suffix = mt.suffix.map(|s| s.as_str().to_ascii_lowercase());
+                match (ty.as_str(), subty.as_str(), suffix.as_deref()) {
+                    #(#match_arms)*
+                    _ => {}
+                }
 
-                Err(axum_accept::AcceptRejection::NoSupportedMediaTypeFound)
+                Err(#krate::ContentTypeRejection::UnsupportedMediaType(
+                    #krate::supported_media_types_header(&parts.method),
+                    SUPPORTED,
+                ))
             }
         }
+
+        impl #type_impl_generics #krate::Dimension for #name #ty_generics #where_clause {
+            const HEADER: #axum::http::HeaderName = #axum::http::header::CONTENT_TYPE;
+        }
     };
 
-    TokenStream::from(expanded)
+    Ok(expanded)
 }
 
-fn get_accept_mediatype(attrs: &[Attribute]) -> String {
-    for attr in attrs {
-        if attr.path().is_ident("accept")
-            && let Meta::List(meta_list) = &attr.meta
-        {
-            for nested in meta_list
-                .parse_args_with(
-                    syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
-                )
-                .expect("Failed to parse args")
+/// This is the proc macro for `Negotiate`.
+#[proc_macro_derive(Negotiate, attributes(negotiate))]
+pub fn derive_negotiate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    negotiate(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn negotiate(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let (type_impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = with_state_param(&input.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Negotiate can only be derived for structs",
+        ));
+    };
+
+    let args = Args::parse(&input.attrs, "negotiate", &["crate"])?;
+    let krate = args.crate_path()?;
+    let axum = quote!(#krate::__private::axum);
+
+    let mut extractions = Vec::new();
+    let mut bindings = Vec::new();
+    let mut headers = Vec::new();
+
+    for (i, field) in data.fields.iter().enumerate() {
+        let ty = &field.ty;
+        let (binding, field_name) = match &field.ident {
+            Some(ident) => (ident.clone(), ident.to_string()),
+            None => (format_ident!("field_{i}"), i.to_string()),
+        };
+
+        extractions.push(quote! {
+            let #binding = match <#ty as #axum::extract::FromRequestParts<S>>::from_request_parts(parts, state).await {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    rejection.push(#field_name, <#ty as #krate::Dimension>::HEADER, e).await;
+                    None
+                }
+            };
+        });
+        bindings.push(binding);
+        headers.push(quote! { <#ty as #krate::Dimension>::HEADER });
+    }
+
+    let construct = match &data.fields {
+        Fields::Named(_) => quote! { #name { #(#bindings),* } },
+        Fields::Unnamed(_) => quote! { #name ( #(#bindings),* ) },
+        Fields::Unit => quote! { #name },
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #axum::extract::FromRequestParts<S> for #name #ty_generics #where_clause {
+            type Rejection = #krate::NegotiateRejection;
+
+            async fn from_request_parts(parts: &mut #axum::http::request::Parts, state: &S) -> Result<Self, Self::Rejection> {
+                let mut rejection = #krate::NegotiateRejection::default();
+                #(#extractions)*
+
+                // all dimensions were extracted if there is no rejection
+                match (#(#bindings,)*) {
+                    (#(Some(#bindings),)*) => Ok(#construct),
+                    _ => Err(rejection),
+                }
+            }
+        }
+
+        impl #type_impl_generics #name #ty_generics #where_clause {
+            /// The value of the `Vary` header for responses negotiated with
+            /// this type.
+            #[must_use]
+            pub fn vary() -> #axum::http::HeaderValue {
+                #krate::vary_header_value(&[#(#headers),*])
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// This is the proc macro for `PreferExtractor`.
+#[proc_macro_derive(PreferExtractor, attributes(prefer))]
+pub fn derive_prefer_extractor(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    prefer_extractor(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn prefer_extractor(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+
+    let (type_impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let generics = with_state_param(&input.generics);
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "PreferExtractor can only be derived for enums",
+        ));
+    };
+
+    let args = Args::parse(&input.attrs, "prefer", &["crate"])?;
+    let krate = args.crate_path()?;
+    let axum = quote!(#krate::__private::axum);
+
+    let has_default = has_default_variant(data);
+
+    // Checks returning the variant if the preference matches
+    let mut checks = Vec::new();
+    // Match arms returning the applied preference of the variant
+    let mut applied_arms = Vec::new();
+
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let variant_args = Args::parse(&variant.attrs, "prefer", &["preference"])?;
+        let preference_lit = variant_args.required_string("preference", variant_name)?;
+        let preference = preference_lit.value();
+        let (preference_name, value) = match preference.split_once('=') {
+            Some((preference_name, value)) => (preference_name.trim(), Some(value.trim())),
+            None => (preference.trim(), None),
+        };
+        if preference_name.is_empty() {
+            return Err(syn::Error::new_spanned(
+                preference_lit,
+                "Please use a preference name",
+            ));
+        }
+
+        check_unit(variant)?;
+
+        // quote encodes None to empty string, so we need to take extra steps
+        let value = if let Some(value) = value {
+            quote! { Some(#value) }
+        } else {
+            quote! { None }
+        };
+        checks.push(quote! {
+            if preference.matches(#preference_name, #value) {
+                return Ok(Some(#name::#variant_name));
+            }
+        });
+        applied_arms.push(quote! {
+            #name::#variant_name => #krate::Preference::new(#preference_name, #value),
+        });
+    }
+
+    let from_request_parts = if has_default {
+        Some(quote! {
+            impl #impl_generics #axum::extract::FromRequestParts<S> for #name #ty_generics #where_clause {
+                type Rejection = #krate::PreferRejection;
+
+                async fn from_request_parts(parts: &mut #axum::http::request::Parts, state: &S) -> Result<Self, Self::Rejection> {
+                    let preference = <Self as #axum::extract::OptionalFromRequestParts<S>>::from_request_parts(parts, state).await?;
+                    Ok(preference.unwrap_or_default())
+                }
+            }
+        })
+    } else {
+        None
+    };
+
+    let expanded = quote! {
+        impl #impl_generics #axum::extract::OptionalFromRequestParts<S> for #name #ty_generics #where_clause {
+            type Rejection = #krate::PreferRejection;
+
+            async fn from_request_parts(parts: &mut #axum::http::request::Parts, _state: &S) -> Result<Option<Self>, Self::Rejection> {
+                #krate::record_vary(&parts.extensions, <Self as #krate::Dimension>::HEADER);
+                for preference in #krate::parse_preferences(&parts.headers)? {
+                    #(#checks)*
+                }
+
+                Ok(None)
+            }
+        }
+
+        #from_request_parts
+
+        impl #type_impl_generics #name #ty_generics #where_clause {
+            /// The `Preference-Applied` header for this preference.
+            #[must_use]
+            pub fn preference_applied(&self) -> #krate::PreferenceApplied {
+                #krate::PreferenceApplied::from(match self {
+                    #(#applied_arms)*
+                })
+            }
+        }
+
+        impl #type_impl_generics #krate::Dimension for #name #ty_generics #where_clause {
+            const HEADER: #axum::http::HeaderName = #krate::PREFER;
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// Add `S: Send + Sync` to the generics, which is needed for the impl generics
+/// of `FromRequestParts`.
+fn with_state_param(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    let s_param = GenericParam::Type(TypeParam {
+        attrs: vec![],
+        ident: Ident::new("S", proc_macro2::Span::call_site()),
+        colon_token: Some(syn::token::Colon::default()),
+        bounds: {
+            let mut bounds = syn::punctuated::Punctuated::new();
+            bounds.push(TypeParamBound::Trait(syn::TraitBound {
+                paren_token: None,
+                modifier: syn::TraitBoundModifier::None,
+                lifetimes: None,
+                path: parse_quote!(Send),
+            }));
+            bounds.push(TypeParamBound::Trait(syn::TraitBound {
+                paren_token: None,
+                modifier: syn::TraitBoundModifier::None,
+                lifetimes: None,
+                path: parse_quote!(Sync),
+            }));
+            bounds
+        },
+        eq_token: None,
+        default: None,
+    });
+    generics.params.push(s_param);
+    generics
+}
+
+fn has_default_variant(data: &DataEnum) -> bool {
+    default_variant(data).is_some()
+}
+
+fn default_variant(data: &DataEnum) -> Option<&Ident> {
+    data.variants
+        .iter()
+        .find(|variant| {
+            variant.attrs.iter().any(|attr| match &attr.meta {
+                Meta::Path(path) => path.is_ident("default"),
+                _ => false,
+            })
+        })
+        .map(|variant| &variant.ident)
+}
+
+/// The type, subtype and suffix of a media type in lowercase, as media types
+/// are case-insensitive.
+fn lowercase_parts(mediatype: &MediaTypeBuf) -> (String, String, Option<String>) {
+    (
+        mediatype.ty().as_str().to_ascii_lowercase(),
+        mediatype.subty().as_str().to_ascii_lowercase(),
+        mediatype.suffix().map(|s| s.as_str().to_ascii_lowercase()),
+    )
+}
+
+/// Reject variants with fields, as only unit variants can be constructed.
+fn check_unit(variant: &syn::Variant) -> syn::Result<()> {
+    match &variant.fields {
+        Fields::Unit => Ok(()),
+        fields => Err(syn::Error::new_spanned(
+            fields,
+            "Only unit variants are supported",
+        )),
+    }
+}
+
+/// The `key = value` and `flag` arguments of all `#[attr_name(...)]`
+/// attributes of an item.
+struct Args(Vec<Meta>);
+
+impl Args {
+    /// Parse the arguments, rejecting keys that are not in `keys`.
+    fn parse(attrs: &[Attribute], attr_name: &str, keys: &[&str]) -> syn::Result<Self> {
+        let mut args = Vec::new();
+        for attr in attrs {
+            if !attr.path().is_ident(attr_name) {
+                continue;
+            }
+            for meta in
+                attr.parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated)?
             {
-                if let syn::Meta::NameValue(name_value) = nested
-                    && name_value.path.is_ident("mediatype")
-                    && let syn::Expr::Lit(expr_lit) = &name_value.value
-                    && let Lit::Str(lit_str) = &expr_lit.lit
-                {
-                    return lit_str.value();
+                if !keys.iter().any(|key| meta.path().is_ident(key)) {
+                    let key = meta
+                        .path()
+                        .get_ident()
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    return Err(syn::Error::new_spanned(
+                        meta.path(),
+                        format!(
+                            "Unknown key {key} in #[{attr_name}(...)], expected one of: {}",
+                            keys.join(", ")
+                        ),
+                    ));
                 }
+                args.push(meta);
             }
         }
+        Ok(Self(args))
+    }
+
+    /// Get the value of `key = value`, if set.
+    fn value(&self, key: &str) -> Option<&Expr> {
+        self.0.iter().find_map(|meta| match meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident(key) => Some(&name_value.value),
+            _ => None,
+        })
+    }
+
+    /// Check whether `key` is set as a flag, without a value.
+    fn flag(&self, key: &str) -> bool {
+        self.0
+            .iter()
+            .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident(key)))
+    }
+
+    /// Get the string literal of `key = "..."`, if set.
+    fn string(&self, key: &str) -> syn::Result<Option<LitStr>> {
+        match self.value(key) {
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Str(lit_str),
+                ..
+            })) => Ok(Some(lit_str.clone())),
+            Some(value) => Err(syn::Error::new_spanned(
+                value,
+                format!(r#"Expected a string, e.g. {key} = "...""#),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the path of axum-accept from `crate = "..."`, for renamed
+    /// dependencies and re-exports.
+    fn crate_path(&self) -> syn::Result<syn::Path> {
+        match self.string("crate")? {
+            Some(lit) => lit.parse(),
+            None => Ok(parse_quote!(::axum_accept)),
+        }
+    }
+
+    /// Get the string literal of `key = "..."`, which the variant needs.
+    fn required_string(&self, key: &str, variant_name: &Ident) -> syn::Result<LitStr> {
+        self.string(key)?.ok_or_else(|| {
+            syn::Error::new_spanned(variant_name, format!(r#"Missing {key} = "...""#))
+        })
+    }
+
+    /// Get the concrete media type of `mediatype = "..."` with its literal.
+    fn mediatype(&self, variant_name: &Ident) -> syn::Result<(LitStr, MediaTypeBuf)> {
+        let lit = self.required_string("mediatype", variant_name)?;
+        let mediatype = MediaTypeBuf::from_string(lit.value())
+            .map_err(|e| syn::Error::new_spanned(&lit, format!("Invalid media type: {e}")))?;
+        if mediatype.ty() == "*" || mediatype.subty() == "*" {
+            return Err(syn::Error::new_spanned(
+                &lit,
+                "Please use a concrete media type",
+            ));
+        }
+        Ok((lit, mediatype))
+    }
+
+    /// Get the integer of `key = N`, if set.
+    fn int(&self, key: &str) -> syn::Result<Option<i32>> {
+        let Some(value) = self.value(key) else {
+            return Ok(None);
+        };
+        // negative integers are unary expressions
+        let (negative, expr) = match value {
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr,
+                ..
+            }) => (true, &**expr),
+            expr => (false, expr),
+        };
+        let Expr::Lit(ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        }) = expr
+        else {
+            return Err(syn::Error::new_spanned(
+                value,
+                format!("Expected an integer, e.g. {key} = 1"),
+            ));
+        };
+        let int: i32 = lit_int.base10_parse()?;
+        Ok(Some(if negative { -int } else { int }))
+    }
+
+    /// Get the variant of `key = Variant`, if set.
+    fn variant(&self, key: &str, data: &DataEnum) -> syn::Result<Option<Ident>> {
+        let Some(value) = self.value(key) else {
+            return Ok(None);
+        };
+        if let Expr::Path(expr_path) = value
+            && let Some(ident) = expr_path.path.get_ident()
+            && data.variants.iter().any(|variant| variant.ident == *ident)
+        {
+            return Ok(Some(ident.clone()));
+        }
+
+        Err(syn::Error::new_spanned(
+            value,
+            format!("{key} has to be a variant of the enum"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accept_extractor, prefer_extractor};
+    use syn::{DeriveInput, parse_quote};
+
+    fn accept_error(input: &DeriveInput) -> String {
+        accept_extractor(input).map_or_else(|e| e.to_string(), |_| String::new())
+    }
+
+    #[test]
+    fn test_accept_extractor_errors() {
+        assert_eq!(
+            "AcceptExtractor can only be derived for enums",
+            accept_error(&parse_quote! {
+                struct Accept;
+            })
+        );
+        assert_eq!(
+            r#"Missing mediatype = "...""#,
+            accept_error(&parse_quote! {
+                enum Accept {
+                    Json,
+                }
+            })
+        );
+        assert!(
+            accept_error(&parse_quote! {
+                enum Accept {
+                    #[accept(mediatype = "json")]
+                    Json,
+                }
+            })
+            .starts_with("Invalid media type")
+        );
+        assert_eq!(
+            "Please use a concrete media type",
+            accept_error(&parse_quote! {
+                enum Accept {
+                    #[accept(mediatype = "text/*")]
+                    Text,
+                }
+            })
+        );
+        assert_eq!(
+            "Only unit variants are supported",
+            accept_error(&parse_quote! {
+                enum Accept {
+                    #[accept(mediatype = "application/json")]
+                    Json(String),
+                }
+            })
+        );
+        assert_eq!(
+            r#"#[accept(on_unacceptable = "default")] requires a #[default] variant"#,
+            accept_error(&parse_quote! {
+                #[accept(on_unacceptable = "default")]
+                enum Accept {
+                    #[accept(mediatype = "application/json")]
+                    Json,
+                }
+            })
+        );
+        assert_eq!(
+            "on_any has to be a variant of the enum",
+            accept_error(&parse_quote! {
+                #[accept(on_any = Html)]
+                enum Accept {
+                    #[accept(mediatype = "application/json")]
+                    Json,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_accept_extractor_duplicate_media_type() {
+        assert_eq!(
+            "Duplicate media type text/plain, already used by Plain",
+            accept_error(&parse_quote! {
+                enum Accept {
+                    #[accept(mediatype = "text/plain")]
+                    Plain,
+                    #[accept(mediatype = "text/plain; charset=utf-8")]
+                    Utf8,
+                }
+            })
+        );
     }
 
-    panic!(r#"Missing #[accept(mediatype = "...")]"#)
+    #[test]
+    fn test_accept_extractor_unknown_key() {
+        assert_eq!(
+            "Unknown key media_type in #[accept(...)], expected one of: mediatype, priority",
+            accept_error(&parse_quote! {
+                enum Accept {
+                    #[accept(media_type = "application/json")]
+                    Json,
+                }
+            })
+        );
+        assert!(
+            accept_error(&parse_quote! {
+                #[accept(lenient, fallback = Json)]
+                enum Accept {
+                    #[accept(mediatype = "application/json")]
+                    Json,
+                }
+            })
+            .starts_with("Unknown key fallback")
+        );
+    }
+
+    #[test]
+    fn test_prefer_extractor_errors() {
+        let input: DeriveInput = parse_quote! {
+            enum Prefer {
+                #[prefer(preference = "=strict")]
+                Strict,
+            }
+        };
+        assert_eq!(
+            "Please use a preference name",
+            prefer_extractor(&input).map_or_else(|e| e.to_string(), |_| String::new())
+        );
+    }
 }
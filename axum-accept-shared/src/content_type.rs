@@ -0,0 +1,163 @@
+//! Types and functions for negotiating the `Content-Type` of request bodies.
+use std::fmt::Display;
+
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header::ToStrError},
+    response::{IntoResponse, Response},
+};
+use mediatype::{MediaType, MediaTypeError, Name, ReadParams};
+
+/// The `Accept-Post` header name (W3C Linked Data Platform).
+pub const ACCEPT_POST: HeaderName = HeaderName::from_static("accept-post");
+
+/// The `Accept-Patch` header name (RFC 5789).
+pub const ACCEPT_PATCH: HeaderName = HeaderName::from_static("accept-patch");
+
+/// The error type returned in the `FromRequestParts` implementations of
+/// `ContentTypeExtractor`.
+#[derive(Debug)]
+pub enum ContentTypeRejection {
+    /// The header could not be converted to a &str.
+    InvalidHeader(ToStrError),
+    /// The media type could not be parsed.
+    InvalidMediaType(MediaTypeError),
+    /// The content type is missing or not supported. .1 contains the
+    /// supported media types, which are advertised in the header .0 if set.
+    UnsupportedMediaType(Option<HeaderName>, &'static [&'static str]),
+}
+
+impl ContentTypeRejection {
+    /// Get the status and message for an error.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::InvalidHeader(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid content-type header: {e}"),
+            ),
+            Self::InvalidMediaType(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid media type in content-type header: {e}"),
+            ),
+            Self::UnsupportedMediaType(_, supported) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!(
+                    "Content-Type header does not contain a supported media type, expected one of: {}",
+                    supported.join(", ")
+                ),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for ContentTypeRejection {
+    fn into_response(self) -> Response {
+        let mut response = self.status_and_message().into_response();
+        if let Self::UnsupportedMediaType(Some(header), supported) = self
+            && let Ok(value) = HeaderValue::from_str(&supported.join(", "))
+        {
+            response.headers_mut().insert(header, value);
+        }
+        response
+    }
+}
+
+impl Display for ContentTypeRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for ContentTypeRejection {}
+
+/// Get the header used to advertise the supported media types in a 415
+/// response for the given method, if there is one.
+#[must_use]
+pub fn supported_media_types_header(method: &Method) -> Option<HeaderName> {
+    match *method {
+        Method::POST => Some(ACCEPT_POST),
+        Method::PATCH => Some(ACCEPT_PATCH),
+        _ => None,
+    }
+}
+
+/// Parse the media type from the content-type header.
+///
+/// # Errors
+///
+/// Returns an error if the content-type header is invalid.
+pub fn parse_content_type(
+    headers: &HeaderMap,
+) -> Result<Option<MediaType<'_>>, ContentTypeRejection> {
+    headers
+        .get("content-type")
+        .map(|header| {
+            let header = header
+                .to_str()
+                .map_err(ContentTypeRejection::InvalidHeader)?;
+            MediaType::parse(header).map_err(ContentTypeRejection::InvalidMediaType)
+        })
+        .transpose()
+}
+
+/// Check whether all of the given parameters are present in the media type.
+/// Values are compared case-insensitively.
+#[must_use]
+pub fn params_match(params: &[(&str, &str)], mt: &MediaType) -> bool {
+    params.iter().all(|(name, value)| {
+        Name::new(name)
+            .and_then(|name| mt.get_param(name))
+            .is_some_and(|actual| actual.unquoted_str().eq_ignore_ascii_case(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContentTypeRejection, params_match, parse_content_type};
+    use axum::http::HeaderMap;
+    use mediatype::{MediaType, media_type};
+
+    #[test]
+    fn test_parse_content_type_missing() {
+        let headers = HeaderMap::new();
+        let mt = parse_content_type(&headers).expect("Missing header should've parsed");
+        assert_eq!(None, mt);
+    }
+
+    #[test]
+    fn test_parse_content_type_invalid_media_type() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "lol".parse()?);
+        match parse_content_type(&headers) {
+            Err(ContentTypeRejection::InvalidMediaType(_)) => {}
+            _ => panic!("expected invalid media type rejection"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_content_type_valid() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json; charset=utf-8".parse()?);
+        let mt = parse_content_type(&headers)
+            .expect("Content-Type header should've parsed correctly")
+            .expect("Content-Type header should be present");
+        assert_eq!(media_type!(APPLICATION / JSON), mt.essence());
+        Ok(())
+    }
+
+    #[test]
+    fn test_params_match() -> Result<(), Box<dyn std::error::Error>> {
+        let mt = MediaType::parse("text/plain; charset=\"UTF-8\"; format=flowed")?;
+        assert!(params_match(&[], &mt));
+        assert!(params_match(&[("charset", "utf-8")], &mt));
+        assert!(params_match(
+            &[("charset", "utf-8"), ("format", "flowed")],
+            &mt
+        ));
+        assert!(!params_match(&[("charset", "latin1")], &mt));
+        assert!(!params_match(&[("delsp", "yes")], &mt));
+        Ok(())
+    }
+}
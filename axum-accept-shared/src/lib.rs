@@ -5,12 +5,29 @@
 #![deny(missing_docs)]
 use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
-use axum::{
-    http::{HeaderMap, StatusCode, header::ToStrError},
-    response::{IntoResponse, Response},
-};
+use axum::http::{HeaderMap, StatusCode, header::ToStrError};
+#[cfg(not(feature = "problem-json"))]
+use axum::response::{IntoResponse, Response};
 use mediatype::{MediaType, MediaTypeError, MediaTypeList, Name, ReadParams, names::_STAR};
 
+mod content_type;
+pub use content_type::{
+    ACCEPT_PATCH, ACCEPT_POST, ContentTypeRejection, params_match, parse_content_type,
+    supported_media_types_header,
+};
+
+mod negotiate;
+pub use negotiate::{Dimension, DimensionRejection, NegotiateRejection, vary_header_value};
+
+mod not_acceptable;
+pub use not_acceptable::RejectionBody;
+
+#[cfg(feature = "problem-json")]
+mod problem;
+
+mod prefer;
+pub use prefer::{PREFER, PREFERENCE_APPLIED, PreferRejection, Preference, parse_preferences};
+
 /// The error type returned in the `FromRequestParts` implementations.
 #[derive(Debug)]
 pub enum AcceptRejection {
@@ -20,11 +37,28 @@ pub enum AcceptRejection {
     InvalidMediaType(usize, MediaTypeError),
     /// Invalid q parameter
     InvalidQ(usize, <f64 as FromStr>::Err),
-    /// No supported media type was found.
-    NoSupportedMediaTypeFound,
+    /// No supported media type was found. Create it with
+    /// [`AcceptRejection::not_acceptable`].
+    #[non_exhaustive]
+    NoSupportedMediaTypeFound {
+        /// The media types that are available.
+        offered: &'static [&'static str],
+        /// The format of the response body, chosen from the `Accept` header.
+        body: RejectionBody,
+    },
 }
 
 impl AcceptRejection {
+    /// Create a [`AcceptRejection::NoSupportedMediaTypeFound`] listing the
+    /// offered media types, in a body format the client accepts.
+    #[must_use]
+    pub fn not_acceptable(headers: &HeaderMap, offered: &'static [&'static str]) -> Self {
+        Self::NoSupportedMediaTypeFound {
+            offered,
+            body: RejectionBody::from_headers(headers),
+        }
+    }
+
     /// Get the status and message for an error.
     #[must_use]
     pub fn status_and_message(&self) -> (StatusCode, String) {
@@ -41,7 +75,14 @@ impl AcceptRejection {
                 StatusCode::BAD_REQUEST,
                 format!("Invalid q parameter in accept header at index {i}: {e}"),
             ),
-            Self::NoSupportedMediaTypeFound => (
+            Self::NoSupportedMediaTypeFound { offered, .. } if !offered.is_empty() => (
+                StatusCode::NOT_ACCEPTABLE,
+                format!(
+                    "Accept header does not contain supported media types, available: {}",
+                    offered.join(", ")
+                ),
+            ),
+            Self::NoSupportedMediaTypeFound { .. } => (
                 StatusCode::NOT_ACCEPTABLE,
                 "Accept header does not contain supported media types".to_string(),
             ),
@@ -49,9 +90,18 @@ impl AcceptRejection {
     }
 }
 
+/// With the `problem-json` feature, rejections are rendered as problem details
+/// instead.
+#[cfg(not(feature = "problem-json"))]
 impl IntoResponse for AcceptRejection {
     fn into_response(self) -> Response {
-        self.status_and_message().into_response()
+        match self {
+            Self::NoSupportedMediaTypeFound { offered, body } => {
+                let (_, message) = self.status_and_message();
+                body.not_acceptable(&message, offered)
+            }
+            _ => self.status_and_message().into_response(),
+        }
     }
 }
 
@@ -70,38 +120,45 @@ impl std::error::Error for AcceptRejection {}
 ///
 /// Returns an error if the accept header is invalid or no match was found.
 pub fn parse_mediatypes(headers: &HeaderMap) -> Result<Vec<MediaType<'_>>, AcceptRejection> {
-    let accept_header = headers
-        .get("accept")
-        .map(|header| header.to_str())
-        .transpose()
-        .map_err(AcceptRejection::InvalidHeader)?
-        .unwrap_or_default();
+    parse(headers, false)
+}
 
-    let Some(q_name) = Name::new("q") else {
-        unreachable!()
+/// Parse and process the media types from the accept header, skipping
+/// invalid entries and reporting them with `tracing::debug!`. An invalid
+/// header is treated like a missing one.
+#[must_use]
+pub fn parse_mediatypes_lenient(headers: &HeaderMap) -> Vec<MediaType<'_>> {
+    // lenient parsing doesn't fail
+    parse(headers, true).unwrap_or_default()
+}
+
+fn parse(headers: &HeaderMap, lenient: bool) -> Result<Vec<MediaType<'_>>, AcceptRejection> {
+    let accept_header = match headers.get("accept").map(|header| header.to_str()) {
+        Some(Ok(accept_header)) => accept_header,
+        Some(Err(e)) if lenient => {
+            tracing::debug!("Ignoring invalid accept header: {e}");
+            ""
+        }
+        Some(Err(e)) => return Err(AcceptRejection::InvalidHeader(e)),
+        None => "",
     };
 
     let mut list = MediaTypeList::new(accept_header)
         .enumerate()
         .map(|(i, mt)| match mt {
             // validate q parameter and add it as u16 for sorting
-            Ok(mt) => Ok(match mt.get_param(q_name) {
-                Some(q_str) => {
-                    let q: f64 = q_str
-                        .as_str()
-                        .parse::<f64>()
-                        .map_err(|e| AcceptRejection::InvalidQ(i, e))?
-                        .clamp(0.0, 1.0);
-
-                    // q is clamped to 0.0-1.0 so nothing can happen here
-                    #[allow(clippy::cast_possible_truncation)]
-                    #[allow(clippy::cast_sign_loss)]
-                    ((q * 1000.0) as u16, mt)
-                }
-                None => (1000, mt),
-            }),
+            Ok(mt) => parse_q(&mt)
+                .map(|q| (q, mt))
+                .map_err(|e| AcceptRejection::InvalidQ(i, e)),
             Err(e) => Err(AcceptRejection::InvalidMediaType(i, e)),
         })
+        .filter(|entry| match entry {
+            Err(rejection) if lenient => {
+                tracing::debug!("Skipping invalid accept header entry: {rejection}");
+                false
+            }
+            _ => true,
+        })
         .collect::<Result<Vec<(u16, MediaType)>, AcceptRejection>>()?;
 
     list.sort_by(|(a_q, a_mt), (b_q, b_mt)| {
@@ -135,9 +192,31 @@ pub fn parse_mediatypes(headers: &HeaderMap) -> Result<Vec<MediaType<'_>>, Accep
     Ok(list.into_iter().map(|(_, mt)| mt).collect())
 }
 
+/// The q parameter of a media type in thousandths, or 1000 if it's missing or
+/// invalid.
+#[must_use]
+pub fn quality(mt: &MediaType) -> u16 {
+    parse_q(mt).unwrap_or(1000)
+}
+
+fn parse_q(mt: &MediaType) -> Result<u16, <f64 as FromStr>::Err> {
+    let Some(q_name) = Name::new("q") else {
+        unreachable!()
+    };
+    let Some(q_str) = mt.get_param(q_name) else {
+        return Ok(1000);
+    };
+    let q = q_str.as_str().parse::<f64>()?.clamp(0.0, 1.0);
+
+    // q is clamped to 0.0-1.0 so nothing can happen here
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    Ok((q * 1000.0) as u16)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{AcceptRejection, parse_mediatypes};
+    use super::{AcceptRejection, parse_mediatypes, parse_mediatypes_lenient};
     use axum::http::HeaderMap;
     use mediatype::media_type;
 
@@ -243,4 +322,22 @@ mod tests {
             list
         );
     }
+
+    #[test]
+    fn test_parse_mediatype_lenient() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "accept",
+            "text/html;q=,lol,application/json;q=0.9,*/*; charset=utf-8;".parse()?,
+        );
+        let list = parse_mediatypes_lenient(&headers);
+        assert_eq!(2, list.len());
+        assert_eq!(media_type!(_STAR / _STAR), list[0].essence());
+        assert_eq!(media_type!(APPLICATION / JSON), list[1].essence());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("accept", "‎ ".parse()?);
+        assert!(parse_mediatypes_lenient(&headers).is_empty());
+        Ok(())
+    }
 }
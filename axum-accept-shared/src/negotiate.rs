@@ -0,0 +1,219 @@
+//! Types for negotiating several dimensions (media type, language, ...) at
+//! once.
+use std::fmt::Display;
+
+use axum::{
+    body::{Bytes, to_bytes},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+/// An extractor that negotiates on a single request header, e.g. `Accept`.
+///
+/// This is implemented by the derived extractors and can be implemented for
+/// custom ones, so they can be used as fields of a derived `Negotiate`.
+pub trait Dimension {
+    /// The request header the representation depends on, which belongs in
+    /// the `Vary` header of the response.
+    const HEADER: HeaderName;
+}
+
+/// A failed dimension of a [`NegotiateRejection`].
+#[derive(Debug)]
+pub struct DimensionRejection {
+    /// The name of the field that failed to extract.
+    pub field: &'static str,
+    /// The header the field is negotiated on.
+    pub header: HeaderName,
+    /// The status of the rejection of the field.
+    pub status: StatusCode,
+    /// The headers of the rejection of the field.
+    pub headers: HeaderMap,
+    /// The body of the rejection of the field.
+    pub body: Bytes,
+}
+
+impl DimensionRejection {
+    /// Get the message of the rejection: the body if it is plain text,
+    /// otherwise the reason phrase of the status.
+    #[must_use]
+    pub fn message(&self) -> String {
+        let is_plain = self
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/plain"));
+        if is_plain {
+            String::from_utf8_lossy(&self.body).into_owned()
+        } else {
+            self.status
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string()
+        }
+    }
+}
+
+/// The error type returned in the `FromRequestParts` implementations of
+/// `Negotiate`, listing all dimensions that failed.
+#[derive(Debug, Default)]
+pub struct NegotiateRejection {
+    /// The failed dimensions, in field order.
+    pub failures: Vec<DimensionRejection>,
+}
+
+impl NegotiateRejection {
+    /// Record a failed dimension with the response of its rejection.
+    pub async fn push<R>(&mut self, field: &'static str, header: HeaderName, rejection: R)
+    where
+        R: IntoResponse,
+    {
+        let (parts, body) = rejection.into_response().into_parts();
+        // rejections are rendered in memory, reading them can't fail
+        let body = to_bytes(body, usize::MAX).await.unwrap_or_default();
+        self.failures.push(DimensionRejection {
+            field,
+            header,
+            status: parts.status,
+            headers: parts.headers,
+            body,
+        });
+    }
+
+    /// Get the status and message for an error. The status is the one of the
+    /// first failed dimension.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        let status = self
+            .failures
+            .first()
+            .map_or(StatusCode::BAD_REQUEST, |failure| failure.status);
+        let message = self
+            .failures
+            .iter()
+            .map(|failure| {
+                format!(
+                    "Failed to negotiate {} ({}): {}",
+                    failure.field,
+                    failure.header,
+                    failure.message()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        (status, message)
+    }
+}
+
+/// A single failed dimension responds with its own rejection, several with
+/// the combined messages.
+impl IntoResponse for NegotiateRejection {
+    fn into_response(self) -> Response {
+        if let [failure] = self.failures.as_slice() {
+            return (
+                failure.status,
+                failure.headers.clone(),
+                failure.body.clone(),
+            )
+                .into_response();
+        }
+        self.status_and_message().into_response()
+    }
+}
+
+impl Display for NegotiateRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for NegotiateRejection {}
+
+/// Build a `Vary` header value from the given headers, skipping duplicates.
+#[must_use]
+pub fn vary_header_value(headers: &[HeaderName]) -> HeaderValue {
+    let mut names: Vec<&str> = Vec::with_capacity(headers.len());
+    for header in headers {
+        if !names.contains(&header.as_str()) {
+            names.push(header.as_str());
+        }
+    }
+    // header names only consist of visible ascii, so this can't fail
+    let Ok(value) = HeaderValue::from_str(&names.join(", ")) else {
+        unreachable!()
+    };
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NegotiateRejection, vary_header_value};
+    use axum::{
+        body::to_bytes,
+        http::{StatusCode, header},
+        response::IntoResponse,
+    };
+
+    #[test]
+    fn test_vary_header_value() {
+        let value = vary_header_value(&[
+            header::ACCEPT,
+            header::ACCEPT_LANGUAGE,
+            header::ACCEPT,
+            header::ACCEPT_ENCODING,
+        ]);
+        assert_eq!("accept, accept-language, accept-encoding", value);
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejection() -> Result<(), Box<dyn std::error::Error>> {
+        let mut rejection = NegotiateRejection::default();
+        // rejections only need to implement IntoResponse
+        rejection
+            .push(
+                "format",
+                header::ACCEPT,
+                (
+                    StatusCode::NOT_ACCEPTABLE,
+                    "Accept header does not contain supported media types",
+                ),
+            )
+            .await;
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, response.status());
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("Accept header does not contain supported media types", body);
+
+        let mut rejection = NegotiateRejection::default();
+        rejection
+            .push(
+                "format",
+                header::ACCEPT,
+                (
+                    StatusCode::NOT_ACCEPTABLE,
+                    "Accept header does not contain supported media types",
+                ),
+            )
+            .await;
+        rejection
+            .push(
+                "lang",
+                header::ACCEPT_LANGUAGE,
+                (
+                    StatusCode::NOT_ACCEPTABLE,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    r#"{"message":"unsupported language"}"#,
+                ),
+            )
+            .await;
+        let (status, message) = rejection.status_and_message();
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, status);
+        assert_eq!(
+            "Failed to negotiate format (accept): Accept header does not contain supported media types\n\
+             Failed to negotiate lang (accept-language): Not Acceptable",
+            message
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+//! Rendering 406 responses that list the available representations.
+use std::fmt::Write;
+
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::parse_mediatypes;
+
+/// The format of a rejection body, chosen from the `Accept` header so the
+/// client can read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RejectionBody {
+    /// `text/plain`, for everyone else.
+    #[default]
+    Plain,
+    /// `application/json`, for API clients.
+    Json,
+    /// `text/html`, for browsers.
+    Html,
+}
+
+impl RejectionBody {
+    /// Choose the body format from the `Accept` header. Invalid headers get
+    /// plain text.
+    #[must_use]
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let Ok(mediatypes) = parse_mediatypes(headers) else {
+            return Self::Plain;
+        };
+        for mt in mediatypes {
+            let is_json = (mt.ty == "application" && mt.subty == "json")
+                || mt.suffix.is_some_and(|suffix| suffix == "json");
+            if is_json {
+                return Self::Json;
+            }
+            let is_html = (mt.ty == "text" && mt.subty == "html")
+                || (mt.ty == "application" && mt.subty == "xhtml");
+            if is_html {
+                return Self::Html;
+            }
+            if mt.ty == "text" || mt.ty == "*" {
+                return Self::Plain;
+            }
+        }
+        Self::Plain
+    }
+
+    /// Render a 406 response with the message and the offered media types.
+    ///
+    /// The plain text message is expected to list them already, the JSON body
+    /// has them in `offered` and the HTML body as a list.
+    #[must_use]
+    pub fn not_acceptable(self, message: &str, offered: &[&str]) -> Response {
+        let (content_type, body) = match self {
+            Self::Plain => ("text/plain; charset=utf-8", message.to_string()),
+            Self::Json => {
+                let offered = offered
+                    .iter()
+                    .map(|mt| json_string(mt))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                (
+                    "application/json",
+                    format!(
+                        r#"{{"message":{},"offered":[{offered}]}}"#,
+                        json_string(message)
+                    ),
+                )
+            }
+            Self::Html => {
+                let mut list = String::new();
+                for mt in offered {
+                    // writing to a string can't fail
+                    let _ = write!(list, "<li><code>{}</code></li>", html_escape(mt));
+                }
+                (
+                    "text/html; charset=utf-8",
+                    format!(
+                        "<!DOCTYPE html><html><head><title>406 Not Acceptable</title></head>\
+                         <body><h1>406 Not Acceptable</h1><p>{}</p><ul>{list}</ul></body></html>",
+                        html_escape(message)
+                    ),
+                )
+            }
+        };
+        (
+            StatusCode::NOT_ACCEPTABLE,
+            [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// Quote and escape a string for JSON.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Escape a string for HTML text.
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RejectionBody;
+    use axum::{
+        body::to_bytes,
+        http::{HeaderMap, HeaderValue, StatusCode, header},
+    };
+
+    fn body_for(accept: &'static str) -> RejectionBody {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(accept));
+        RejectionBody::from_headers(&headers)
+    }
+
+    #[test]
+    fn test_rejection_body_from_headers() {
+        assert_eq!(RejectionBody::Json, body_for("application/problem+json"));
+        assert_eq!(
+            RejectionBody::Html,
+            body_for("text/html,application/xhtml+xml,*/*;q=0.8")
+        );
+        assert_eq!(RejectionBody::Plain, body_for("*/*"));
+        assert_eq!(RejectionBody::Plain, body_for("image/png"));
+        assert_eq!(
+            RejectionBody::Plain,
+            RejectionBody::from_headers(&HeaderMap::new())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_body_not_acceptable() -> Result<(), Box<dyn std::error::Error>> {
+        let offered = &["application/json", "text/html"];
+        let message = "Accept header does not contain supported media types, available: application/json, text/html";
+
+        let response = RejectionBody::Plain.not_acceptable(message, offered);
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, response.status());
+        assert_eq!(
+            "text/plain; charset=utf-8",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(message, body);
+
+        let response = RejectionBody::Json.not_acceptable(message, offered);
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, response.status());
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(
+            format!(r#"{{"message":"{message}","offered":["application/json","text/html"]}}"#),
+            body
+        );
+
+        let response = RejectionBody::Html.not_acceptable(message, offered);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let body = std::str::from_utf8(&body)?;
+        assert!(
+            body.contains("<li><code>application/json</code></li><li><code>text/html</code></li>")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejection_body_json_string() {
+        assert_eq!(r#""a\"b\\c\u0001""#, super::json_string("a\"b\\c\u{1}"));
+    }
+}
@@ -0,0 +1,284 @@
+//! Types and functions for the `Prefer` header (RFC 7240).
+use std::{fmt::Display, iter::Peekable, str::Chars};
+
+use axum::{
+    http::{HeaderMap, HeaderName, StatusCode, header::ToStrError},
+    response::{IntoResponse, Response},
+};
+
+/// The `Prefer` header name (RFC 7240).
+pub const PREFER: HeaderName = HeaderName::from_static("prefer");
+
+/// The `Preference-Applied` header name (RFC 7240).
+pub const PREFERENCE_APPLIED: HeaderName = HeaderName::from_static("preference-applied");
+
+/// The error type returned in the `FromRequestParts` implementations of
+/// `Prefer` and `PreferExtractor`.
+#[derive(Debug)]
+pub enum PreferRejection {
+    /// The header could not be converted to a &str.
+    InvalidHeader(ToStrError),
+}
+
+impl PreferRejection {
+    /// Get the status and message for an error.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::InvalidHeader(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid prefer header: {e}"),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for PreferRejection {
+    fn into_response(self) -> Response {
+        self.status_and_message().into_response()
+    }
+}
+
+impl Display for PreferRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for PreferRejection {}
+
+/// A single preference of a `Prefer` header, like `return=minimal` or
+/// `respond-async`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preference {
+    /// The lowercase name of the preference.
+    pub name: String,
+    /// The unquoted value of the preference, if set.
+    pub value: Option<String>,
+    /// The parameters of the preference, with lowercase names and unquoted
+    /// values.
+    pub params: Vec<(String, Option<String>)>,
+}
+
+impl Preference {
+    /// Create a preference without parameters.
+    #[must_use]
+    pub fn new(name: &str, value: Option<&str>) -> Self {
+        Self {
+            name: name.to_ascii_lowercase(),
+            value: value.map(str::to_string),
+            params: Vec::new(),
+        }
+    }
+
+    /// Check whether this preference has the given name and value. Both are
+    /// compared case-insensitively, an empty value equals no value.
+    #[must_use]
+    pub fn matches(&self, name: &str, value: Option<&str>) -> bool {
+        let own_value = self.value.as_deref().filter(|value| !value.is_empty());
+        let value = value.filter(|value| !value.is_empty());
+        self.name.eq_ignore_ascii_case(name)
+            && match (own_value, value) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl Display for Preference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(value) = &self.value {
+            write!(f, "={}", quote_if_needed(value))?;
+        }
+        for (name, value) in &self.params {
+            write!(f, "; {name}")?;
+            if let Some(value) = value {
+                write!(f, "={}", quote_if_needed(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse the preferences of all prefer headers, in order.
+///
+/// Empty and malformed preferences are skipped, as RFC 7240 asks servers to
+/// ignore what they don't understand.
+///
+/// # Errors
+///
+/// Returns an error if a prefer header is no valid string.
+pub fn parse_preferences(headers: &HeaderMap) -> Result<Vec<Preference>, PreferRejection> {
+    let mut preferences = Vec::new();
+    for header in headers.get_all(PREFER) {
+        let header = header.to_str().map_err(PreferRejection::InvalidHeader)?;
+        preferences.extend(parse_header(header));
+    }
+    Ok(preferences)
+}
+
+/// Parse the comma-separated preferences of one prefer header, each a list of
+/// `name[=value]` pairs separated by semicolons. Values can be quoted strings
+/// (RFC 9110), which may contain separators and quoted pairs like `\"`.
+fn parse_header(header: &str) -> Vec<Preference> {
+    let mut preferences = Vec::new();
+    let mut chars = header.chars().peekable();
+    let (mut params, mut malformed) = (Vec::new(), false);
+    loop {
+        let name = take_until(&mut chars, &[',', ';', '=']);
+        let mut value = None;
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+            if chars.next_if_eq(&'"').is_some() {
+                value = parse_quoted(&mut chars);
+                // nothing may follow the closing quote but whitespace
+                let rest = take_until(&mut chars, &[',', ';']);
+                malformed |= value.is_none() || !rest.trim().is_empty();
+            } else {
+                value = Some(take_until(&mut chars, &[',', ';']).trim().to_string());
+            }
+        }
+        let name = name.trim();
+        if !name.is_empty() {
+            params.push((name.to_ascii_lowercase(), value));
+        }
+
+        if chars.next_if_eq(&';').is_some() {
+            continue;
+        }
+        // the end of a preference, at a comma or the end of the header
+        let mut params = std::mem::take(&mut params).into_iter();
+        if !std::mem::take(&mut malformed)
+            && let Some((name, value)) = params.next()
+        {
+            preferences.push(Preference {
+                name,
+                value,
+                params: params.collect(),
+            });
+        }
+        if chars.next().is_none() {
+            return preferences;
+        }
+    }
+}
+
+/// Take the characters up to the next of `stop`.
+fn take_until(chars: &mut Peekable<Chars<'_>>, stop: &[char]) -> String {
+    std::iter::from_fn(|| chars.next_if(|c| !stop.contains(c))).collect()
+}
+
+/// Parse the rest of a quoted string after the opening quote, resolving quoted
+/// pairs. Returns `None` if the closing quote is missing.
+fn parse_quoted(chars: &mut Peekable<Chars<'_>>) -> Option<String> {
+    let mut unquoted = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(unquoted),
+            '\\' => unquoted.push(chars.next()?),
+            c => unquoted.push(c),
+        }
+    }
+    None
+}
+
+/// Quote a value if it's not a valid token.
+fn quote_if_needed(value: &str) -> String {
+    let is_token = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+    if is_token {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preference, parse_preferences};
+    use axum::http::HeaderMap;
+
+    #[test]
+    fn test_parse_preferences() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.append("prefer", "return=minimal, respond-async".parse()?);
+        headers.append(
+            "prefer",
+            r#"Wait=10, foo="bar, \"baz\""; qux; a=b,,"#.parse()?,
+        );
+        let preferences = parse_preferences(&headers).expect("Prefer header should've parsed");
+        assert_eq!(
+            vec![
+                Preference::new("return", Some("minimal")),
+                Preference::new("respond-async", None),
+                Preference::new("wait", Some("10")),
+                Preference {
+                    name: "foo".to_string(),
+                    value: Some(r#"bar, "baz""#.to_string()),
+                    params: vec![
+                        ("qux".to_string(), None),
+                        ("a".to_string(), Some("b".to_string()))
+                    ],
+                },
+            ],
+            preferences
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_preferences_quoted() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            "prefer",
+            r#"a="x\"; y", b= "semi;colon, comma"; c="\\", d=plain"#.parse()?,
+        );
+        let preferences = parse_preferences(&headers)?;
+        assert_eq!(
+            vec![
+                Preference::new("a", Some(r#"x"; y"#)),
+                Preference {
+                    name: "b".to_string(),
+                    value: Some("semi;colon, comma".to_string()),
+                    params: vec![("c".to_string(), Some("\\".to_string()))],
+                },
+                Preference::new("d", Some("plain")),
+            ],
+            preferences
+        );
+
+        // malformed quoted strings skip the preference
+        let mut headers = HeaderMap::new();
+        headers.append("prefer", r#"a="x"y, b=1, c="unterminated\", d=2"#.parse()?);
+        assert_eq!(
+            vec![Preference::new("b", Some("1"))],
+            parse_preferences(&headers)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_preference_matches() {
+        let preference = Preference::new("return", Some("Minimal"));
+        assert!(preference.matches("RETURN", Some("minimal")));
+        assert!(!preference.matches("return", Some("representation")));
+        assert!(!preference.matches("return", None));
+        assert!(Preference::new("respond-async", Some("")).matches("respond-async", None));
+    }
+
+    #[test]
+    fn test_preference_display() {
+        let mut preference = Preference::new("foo", Some("bar baz"));
+        preference.params.push(("qux".to_string(), None));
+        assert_eq!(r#"foo="bar baz"; qux"#, preference.to_string());
+        assert_eq!(
+            "return=minimal",
+            Preference::new("return", Some("minimal")).to_string()
+        );
+    }
+}
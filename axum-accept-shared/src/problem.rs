@@ -0,0 +1,130 @@
+//! `application/problem+json` (RFC 9457) rejection bodies, behind the
+//! `problem-json` feature.
+use axum::{
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::{AcceptRejection, not_acceptable::json_string};
+
+/// The media type of problem details.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// A problem details document with the `about:blank` type, whose title is the
+/// reason phrase of the status.
+pub(crate) struct Problem {
+    status: StatusCode,
+    detail: String,
+    /// Extension members as names and serialized JSON values.
+    members: Vec<(&'static str, String)>,
+}
+
+impl Problem {
+    /// Create a problem without extension members.
+    pub(crate) fn new(status: StatusCode, detail: String) -> Self {
+        Self {
+            status,
+            detail,
+            members: Vec::new(),
+        }
+    }
+
+    /// Add a numeric extension member.
+    pub(crate) fn number(mut self, name: &'static str, value: usize) -> Self {
+        self.members.push((name, value.to_string()));
+        self
+    }
+
+    /// Add an extension member that is a list of strings.
+    pub(crate) fn strings(mut self, name: &'static str, values: &[&str]) -> Self {
+        let values = values
+            .iter()
+            .map(|value| json_string(value))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.members.push((name, format!("[{values}]")));
+        self
+    }
+
+    /// Serialize the problem details.
+    fn body(&self) -> String {
+        let title = self.status.canonical_reason().unwrap_or_default();
+        let mut body = format!(
+            r#"{{"type":"about:blank","title":{},"status":{},"detail":{}"#,
+            json_string(title),
+            self.status.as_u16(),
+            json_string(&self.detail)
+        );
+        for (name, value) in &self.members {
+            body.push(',');
+            body.push_str(&json_string(name));
+            body.push(':');
+            body.push_str(value);
+        }
+        body.push('}');
+        body
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            [(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))],
+            self.body(),
+        )
+            .into_response()
+    }
+}
+
+impl AcceptRejection {
+    /// Render the rejection as problem details, with the index of the
+    /// offending media type or the offered media types as extension members.
+    fn problem(&self) -> Problem {
+        let (status, detail) = self.status_and_message();
+        let problem = Problem::new(status, detail);
+        match self {
+            Self::InvalidHeader(_) => problem,
+            Self::InvalidMediaType(i, _) | Self::InvalidQ(i, _) => problem.number("index", *i),
+            Self::NoSupportedMediaTypeFound { offered, .. } => problem.strings("offered", offered),
+        }
+    }
+}
+
+impl IntoResponse for AcceptRejection {
+    fn into_response(self) -> Response {
+        self.problem().into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AcceptRejection, RejectionBody};
+    use axum::{http::header, response::IntoResponse};
+
+    #[test]
+    fn test_problem() {
+        let rejection = AcceptRejection::NoSupportedMediaTypeFound {
+            offered: &["application/json", "text/html"],
+            body: RejectionBody::Html,
+        };
+        assert_eq!(
+            r#"{"type":"about:blank","title":"Not Acceptable","status":406,"detail":"Accept header does not contain supported media types, available: application/json, text/html","offered":["application/json","text/html"]}"#,
+            rejection.problem().body()
+        );
+        let response = rejection.into_response();
+        assert_eq!(
+            "application/problem+json",
+            response.headers()[header::CONTENT_TYPE]
+        );
+
+        let Err(e) = "lol".parse::<f64>() else {
+            unreachable!()
+        };
+        let rejection = AcceptRejection::InvalidQ(1, e);
+        assert_eq!(
+            r#"{"type":"about:blank","title":"Bad Request","status":400,"detail":"Invalid q parameter in accept header at index 1: invalid float literal","index":1}"#,
+            rejection.problem().body()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! An extractor that deserializes the request body according to its
+//! `Content-Type`.
+use std::fmt::Display;
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request, rejection::BytesRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use axum_accept_shared::{ContentTypeRejection, parse_content_type, supported_media_types_header};
+use serde::de::DeserializeOwned;
+
+use crate::format::{Format, FormatError};
+
+/// Deserializes the request body into `T` in whichever [`Format`] the
+/// `Content-Type` of the request names.
+///
+/// The body is read with axum's `Bytes` extractor, so it respects
+/// `DefaultBodyLimit`.
+///
+/// ```rust
+/// use axum_accept::NegotiatedBody;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct CreateUser {
+///     name: String,
+/// }
+///
+/// async fn create_user(NegotiatedBody(user): NegotiatedBody<CreateUser>) -> String {
+///     user.name
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NegotiatedBody<T>(pub T);
+
+impl<T, S> FromRequest<S> for NegotiatedBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = NegotiatedBodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = parse_content_type(req.headers())?
+            .and_then(|mt| Format::from_media_type(&mt))
+            .ok_or_else(|| {
+                ContentTypeRejection::UnsupportedMediaType(
+                    supported_media_types_header(req.method()),
+                    Format::MEDIA_TYPES,
+                )
+            })?;
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(NegotiatedBodyRejection::Body)?;
+
+        format
+            .deserialize(&bytes)
+            .map(Self)
+            .map_err(|e| NegotiatedBodyRejection::InvalidBody(format, e))
+    }
+}
+
+/// The error type returned in the `FromRequest` implementation of
+/// [`NegotiatedBody`].
+#[derive(Debug)]
+pub enum NegotiatedBodyRejection {
+    /// The content type was invalid or not supported.
+    ContentType(ContentTypeRejection),
+    /// The body could not be read, e.g. because it exceeded the body limit.
+    Body(BytesRejection),
+    /// The body could not be deserialized from the format .0.
+    InvalidBody(Format, FormatError),
+}
+
+impl NegotiatedBodyRejection {
+    /// Get the status and message for an error.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::ContentType(e) => e.status_and_message(),
+            Self::Body(e) => (e.status(), e.body_text()),
+            Self::InvalidBody(format, e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Failed to deserialize the {format} body: {e}"),
+            ),
+        }
+    }
+}
+
+impl From<ContentTypeRejection> for NegotiatedBodyRejection {
+    fn from(e: ContentTypeRejection) -> Self {
+        Self::ContentType(e)
+    }
+}
+
+impl IntoResponse for NegotiatedBodyRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ContentType(e) => e.into_response(),
+            Self::Body(e) => e.into_response(),
+            Self::InvalidBody(..) => self.status_and_message().into_response(),
+        }
+    }
+}
+
+impl Display for NegotiatedBodyRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for NegotiatedBodyRejection {}
+
+#[cfg(test)]
+mod tests {
+    use super::{NegotiatedBody, NegotiatedBodyRejection};
+    use crate::ContentTypeRejection;
+    use axum::{
+        body::Body,
+        extract::{FromRequest, Request},
+        http::{Method, StatusCode},
+        response::IntoResponse,
+    };
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_negotiated_body_json() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"Ferris","age":9}"#))?;
+        let NegotiatedBody(user) = NegotiatedBody::<User>::from_request(req, &()).await?;
+        assert_eq!(
+            User {
+                name: "Ferris".to_string(),
+                age: 9
+            },
+            user
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_negotiated_body_invalid() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"Ferris"}"#))?;
+        let result = NegotiatedBody::<User>::from_request(req, &()).await;
+        let Err(rejection @ NegotiatedBodyRejection::InvalidBody(..)) = result else {
+            panic!("expected invalid body, got {result:?}")
+        };
+        assert_eq!(StatusCode::BAD_REQUEST, rejection.into_response().status());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_body_unsupported() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .method(Method::POST)
+            .header("content-type", "text/csv")
+            .body(Body::from("name,age"))?;
+        let result = NegotiatedBody::<User>::from_request(req, &()).await;
+        let Err(
+            rejection @ NegotiatedBodyRejection::ContentType(
+                ContentTypeRejection::UnsupportedMediaType(..),
+            ),
+        ) = result
+        else {
+            panic!("expected unsupported media type, got {result:?}")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert!(response.headers().contains_key(crate::ACCEPT_POST));
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn test_negotiated_body_limit() -> Result<(), Box<dyn std::error::Error>> {
+        use axum::{Router, extract::DefaultBodyLimit, routing::post};
+        use tower::ServiceExt;
+
+        async fn handler(NegotiatedBody(user): NegotiatedBody<User>) -> String {
+            user.name
+        }
+
+        let app = Router::new()
+            .route("/", post(handler))
+            .layer(DefaultBodyLimit::max(8));
+        let req = Request::builder()
+            .method(Method::POST)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"Ferris","age":9}"#))?;
+        let response = app.oneshot(req).await?;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+        Ok(())
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_negotiated_body_cbor() -> Result<(), Box<dyn std::error::Error>> {
+        let mut body = Vec::new();
+        ciborium::into_writer(
+            &serde_json::json!({ "name": "Ferris", "age": 9 }),
+            &mut body,
+        )?;
+        let req = Request::builder()
+            .header("content-type", "application/cbor")
+            .body(Body::from(body))?;
+        let NegotiatedBody(user) = NegotiatedBody::<User>::from_request(req, &()).await?;
+        assert_eq!("Ferris", user.name);
+        Ok(())
+    }
+
+    #[cfg(feature = "form")]
+    #[tokio::test]
+    async fn test_negotiated_body_form() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from("name=Ferris&age=9"))?;
+        let NegotiatedBody(user) = NegotiatedBody::<User>::from_request(req, &()).await?;
+        assert_eq!(9, user.age);
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! A `text/csv` response (RFC 4180) for rows of serializable values.
+use std::cell::Cell;
+
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use axum_accept_shared::{AcceptRejection, Dimension, parse_mediatypes, quality};
+use mediatype::{MediaType, Name, ReadParams, names::_STAR};
+use serde::Serialize;
+
+use crate::vary::record_vary;
+
+/// Extracts whether the client accepts `text/csv` and if it wants a header
+/// row, from the `header` parameter of RFC 4180.
+///
+/// `text/csv; header=absent` asks for no header row, everything else for
+/// one. `text/csv; q=0` refuses CSV, also if a wildcard would match it. A
+/// missing `Accept` header accepts CSV with a header row. If CSV is not
+/// acceptable, the request is rejected with 406.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcceptCsv {
+    /// Whether the client wants a header row.
+    pub header: bool,
+}
+
+impl AcceptCsv {
+    /// Respond with the rows, with or without a header row as negotiated.
+    pub fn respond<I>(self, rows: I) -> Csv<I::IntoIter>
+    where
+        I: IntoIterator,
+    {
+        Csv::new(rows).header(self.header)
+    }
+
+    /// Get the `header` parameter of a CSV media type, if it is one.
+    #[must_use]
+    pub fn from_media_type(mt: &MediaType) -> Option<Self> {
+        let is_csv =
+            (mt.ty == "text" || mt.ty == _STAR) && (mt.subty == "csv" || mt.subty == _STAR);
+        if !is_csv {
+            return None;
+        }
+        let header = Name::new("header")
+            .and_then(|name| mt.get_param(name))
+            .is_none_or(|value| !value.unquoted_str().eq_ignore_ascii_case("absent"));
+        Some(Self { header })
+    }
+}
+
+impl<S> FromRequestParts<S> for AcceptCsv
+where
+    S: Send + Sync,
+{
+    type Rejection = AcceptRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        record_vary(&parts.extensions, Self::HEADER);
+        let mediatypes = parse_mediatypes(&parts.headers)?;
+        if mediatypes.is_empty() {
+            return Ok(Self { header: true });
+        }
+        // the client explicitly refused CSV
+        let rejected = mediatypes
+            .iter()
+            .any(|mt| quality(mt) == 0 && mt.ty == "text" && mt.subty == "csv");
+        mediatypes
+            .iter()
+            .filter(|mt| !rejected && quality(mt) > 0)
+            .find_map(Self::from_media_type)
+            .ok_or_else(|| AcceptRejection::not_acceptable(&parts.headers, &["text/csv"]))
+    }
+}
+
+impl Dimension for AcceptCsv {
+    const HEADER: HeaderName = header::ACCEPT;
+}
+
+/// Streams the rows as `text/csv`, one CRLF terminated record per row.
+///
+/// With a header row (the default), the field names of the first row are
+/// written before it, so the rows should be structs or maps. Without rows
+/// there are no field names, so an empty export has no header row unless the
+/// names are set with [`Csv::headers`]. Rows are serialized lazily while the
+/// body is sent, which keeps large exports out of memory. If a row fails to
+/// serialize, the body is aborted.
+///
+/// ```rust
+/// use axum_accept::{AcceptCsv, Csv};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Sale {
+///     region: &'static str,
+///     total: u32,
+/// }
+///
+/// async fn export(csv: AcceptCsv) -> Csv<std::vec::IntoIter<Sale>> {
+///     let sales = vec![Sale { region: "north", total: 3 }, Sale { region: "south", total: 5 }];
+///     csv.respond(sales)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Csv<I> {
+    rows: I,
+    header: bool,
+    headers: Option<Vec<String>>,
+}
+
+impl<I> Csv<I> {
+    /// Respond with the rows, including a header row.
+    pub fn new<R>(rows: R) -> Self
+    where
+        R: IntoIterator<IntoIter = I>,
+    {
+        Self {
+            rows: rows.into_iter(),
+            header: true,
+            headers: None,
+        }
+    }
+
+    /// Set the names of the header row, which is then written even if there
+    /// are no rows.
+    #[must_use]
+    pub fn headers<H>(mut self, names: H) -> Self
+    where
+        H: IntoIterator,
+        H::Item: Into<String>,
+    {
+        self.headers = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set whether a header row is written.
+    #[must_use]
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// The `Content-Type` of the response, including the `header` parameter.
+    #[must_use]
+    pub fn content_type(&self) -> HeaderValue {
+        if self.header {
+            HeaderValue::from_static("text/csv; charset=utf-8; header=present")
+        } else {
+            HeaderValue::from_static("text/csv; charset=utf-8; header=absent")
+        }
+    }
+}
+
+impl<I> IntoResponse for Csv<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: Serialize,
+{
+    fn into_response(self) -> Response {
+        let content_type = self.content_type();
+        let headers = self.headers.filter(|_| self.header);
+        let writer = csv::WriterBuilder::new()
+            .has_headers(self.header && headers.is_none())
+            .terminator(csv::Terminator::CRLF)
+            .from_writer(Chunk::default());
+        let records = Records {
+            rows: self.rows,
+            writer,
+            headers,
+        };
+        let body = Body::from_stream(futures_util::stream::iter(records));
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    }
+}
+
+/// The buffer the records are written to, taken after every row.
+#[derive(Default)]
+struct Chunk(Cell<Vec<u8>>);
+
+impl std::io::Write for Chunk {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.get_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes one row per item, after the header row if it is set.
+struct Records<I> {
+    rows: I,
+    writer: csv::Writer<Chunk>,
+    headers: Option<Vec<String>>,
+}
+
+impl<I> Records<I> {
+    fn write(
+        &mut self,
+        record: impl FnOnce(&mut csv::Writer<Chunk>) -> csv::Result<()>,
+    ) -> Result<Vec<u8>, csv::Error> {
+        record(&mut self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer.get_ref().0.take())
+    }
+}
+
+impl<I> Iterator for Records<I>
+where
+    I: Iterator,
+    I::Item: Serialize,
+{
+    type Item = Result<Vec<u8>, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(headers) = self.headers.take() {
+            return Some(self.write(|writer| writer.write_record(&headers)));
+        }
+        let row = self.rows.next()?;
+        Some(self.write(|writer| writer.serialize(row)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AcceptCsv, Csv};
+    use crate::AcceptRejection;
+    use axum::{
+        body::{Body, to_bytes},
+        extract::{FromRequest, Request},
+        http::header,
+        response::IntoResponse,
+    };
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Sale {
+        region: &'static str,
+        total: u32,
+    }
+
+    fn sales() -> Vec<Sale> {
+        vec![
+            Sale {
+                region: "north",
+                total: 3,
+            },
+            Sale {
+                region: "south, east",
+                total: 5,
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_accept_csv() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json, text/csv;header=absent;q=0.5")
+            .body(Body::from(""))?;
+        let csv = AcceptCsv::from_request(req, &()).await?;
+        assert!(!csv.header);
+
+        let req = Request::builder()
+            .header("accept", "text/*")
+            .body(Body::from(""))?;
+        let csv = AcceptCsv::from_request(req, &()).await?;
+        assert!(csv.header);
+
+        let req = Request::builder()
+            .header("accept", "application/json")
+            .body(Body::from(""))?;
+        let result = AcceptCsv::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/csv;q=0, */*;q=0.5")
+            .body(Body::from(""))?;
+        let result = AcceptCsv::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/csv;q=0")
+            .body(Body::from(""))?;
+        let result = AcceptCsv::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csv_response() -> Result<(), Box<dyn std::error::Error>> {
+        let response = Csv::new(sales()).into_response();
+        assert_eq!(
+            "text/csv; charset=utf-8; header=present",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("region,total\r\nnorth,3\r\n\"south, east\",5\r\n", body);
+
+        let response = Csv::new(sales()).header(false).into_response();
+        assert_eq!(
+            "text/csv; charset=utf-8; header=absent",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("north,3\r\n\"south, east\",5\r\n", body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csv_empty() -> Result<(), Box<dyn std::error::Error>> {
+        // without rows, there are no field names for the header row
+        let response = Csv::new(Vec::<Sale>::new()).into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("", body);
+
+        let response = Csv::new(Vec::<Sale>::new())
+            .headers(["region", "total"])
+            .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("region,total\r\n", body);
+
+        let response = Csv::new(sales())
+            .headers(["Region", "Total"])
+            .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("Region,Total\r\nnorth,3\r\n\"south, east\",5\r\n", body);
+
+        let response = Csv::new(sales())
+            .headers(["region", "total"])
+            .header(false)
+            .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("north,3\r\n\"south, east\",5\r\n", body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_csv_invalid_row() {
+        #[derive(Serialize)]
+        struct Nested {
+            sale: Sale,
+        }
+
+        // a nested struct has no header row
+        let response = Csv::new(sales().into_iter().map(|sale| Nested { sale })).into_response();
+        assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+    }
+}
@@ -0,0 +1,552 @@
+//! The built-in serialization formats, each behind its own cargo feature.
+use std::fmt::Display;
+
+use axum_accept_shared::quality;
+use mediatype::{MediaType, names::_STAR};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// A serialization format supported by axum-accept.
+///
+/// Each format is only available if its cargo feature is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// `application/json` and `+json`, requires the `json` feature.
+    #[cfg(feature = "json")]
+    Json,
+    /// `application/x-www-form-urlencoded`, requires the `form` feature.
+    #[cfg(feature = "form")]
+    Form,
+    /// `application/cbor` and `+cbor`, requires the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// `application/msgpack` and its aliases, requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    /// `application/yaml`, its aliases and `+yaml`, requires the `yaml`
+    /// feature.
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// `application/toml`, requires the `toml` feature.
+    #[cfg(feature = "toml")]
+    Toml,
+    /// `application/xml`, `text/xml` and `+xml`, requires the `xml` feature.
+    #[cfg(feature = "xml")]
+    Xml,
+}
+
+/// The error returned if a format fails to (de)serialize a value.
+pub type FormatError = Box<dyn std::error::Error + Send + Sync>;
+
+impl Format {
+    /// The media types of all enabled formats, in the order they are
+    /// advertised.
+    pub const MEDIA_TYPES: &[&str] = &[
+        #[cfg(feature = "json")]
+        "application/json",
+        #[cfg(feature = "form")]
+        "application/x-www-form-urlencoded",
+        #[cfg(feature = "cbor")]
+        "application/cbor",
+        #[cfg(feature = "msgpack")]
+        "application/msgpack",
+        #[cfg(feature = "yaml")]
+        "application/yaml",
+        #[cfg(feature = "toml")]
+        "application/toml",
+        #[cfg(feature = "xml")]
+        "application/xml",
+    ];
+
+    /// All enabled formats, in the order of [`Format::MEDIA_TYPES`].
+    pub const ALL: &[Format] = &[
+        #[cfg(feature = "json")]
+        Self::Json,
+        #[cfg(feature = "form")]
+        Self::Form,
+        #[cfg(feature = "cbor")]
+        Self::Cbor,
+        #[cfg(feature = "msgpack")]
+        Self::MsgPack,
+        #[cfg(feature = "yaml")]
+        Self::Yaml,
+        #[cfg(feature = "toml")]
+        Self::Toml,
+        #[cfg(feature = "xml")]
+        Self::Xml,
+    ];
+
+    /// The media types of the enabled formats responses are serialized in,
+    /// which are all but the request-only form data.
+    pub(crate) const RESPONSE_MEDIA_TYPES: &[&str] = &[
+        #[cfg(feature = "json")]
+        "application/json",
+        #[cfg(feature = "cbor")]
+        "application/cbor",
+        #[cfg(feature = "msgpack")]
+        "application/msgpack",
+        #[cfg(feature = "yaml")]
+        "application/yaml",
+        #[cfg(feature = "toml")]
+        "application/toml",
+        #[cfg(feature = "xml")]
+        "application/xml",
+    ];
+
+    /// The enabled formats responses are serialized in, in the order of
+    /// [`Format::RESPONSE_MEDIA_TYPES`].
+    pub(crate) const RESPONSE_FORMATS: &[Format] = &[
+        #[cfg(feature = "json")]
+        Self::Json,
+        #[cfg(feature = "cbor")]
+        Self::Cbor,
+        #[cfg(feature = "msgpack")]
+        Self::MsgPack,
+        #[cfg(feature = "yaml")]
+        Self::Yaml,
+        #[cfg(feature = "toml")]
+        Self::Toml,
+        #[cfg(feature = "xml")]
+        Self::Xml,
+    ];
+
+    /// Get the format for a media type, if it's enabled.
+    ///
+    /// Structured syntax suffixes like `application/ld+json` map to the format
+    /// of the suffix.
+    #[must_use]
+    pub fn from_media_type(mt: &MediaType) -> Option<Self> {
+        let (ty, subty) = (mt.ty.as_str(), mt.subty.as_str());
+        let suffix = mt.suffix.map(|suffix| suffix.as_str());
+        let is = |expected_ty: &str, expected_subty: &str| {
+            ty.eq_ignore_ascii_case(expected_ty) && subty.eq_ignore_ascii_case(expected_subty)
+        };
+        let has_suffix = |expected: &str| suffix.is_some_and(|s| s.eq_ignore_ascii_case(expected));
+
+        #[cfg(feature = "json")]
+        if is("application", "json") || has_suffix("json") {
+            return Some(Self::Json);
+        }
+        #[cfg(feature = "form")]
+        if is("application", "x-www-form-urlencoded") {
+            return Some(Self::Form);
+        }
+        #[cfg(feature = "cbor")]
+        if is("application", "cbor") || has_suffix("cbor") {
+            return Some(Self::Cbor);
+        }
+        #[cfg(feature = "msgpack")]
+        if is("application", "msgpack")
+            || is("application", "x-msgpack")
+            || is("application", "vnd.msgpack")
+        {
+            return Some(Self::MsgPack);
+        }
+        #[cfg(feature = "yaml")]
+        if is("application", "yaml")
+            || is("application", "x-yaml")
+            || is("text", "yaml")
+            || has_suffix("yaml")
+        {
+            return Some(Self::Yaml);
+        }
+        #[cfg(feature = "toml")]
+        if is("application", "toml") {
+            return Some(Self::Toml);
+        }
+        #[cfg(feature = "xml")]
+        if is("application", "xml") || is("text", "xml") || has_suffix("xml") {
+            return Some(Self::Xml);
+        }
+
+        let _ = (is, has_suffix);
+        None
+    }
+
+    /// The canonical media type of the format.
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json => "application/json",
+            #[cfg(feature = "form")]
+            Self::Form => "application/x-www-form-urlencoded",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "application/cbor",
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => "application/msgpack",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "application/yaml",
+            #[cfg(feature = "toml")]
+            Self::Toml => "application/toml",
+            #[cfg(feature = "xml")]
+            Self::Xml => "application/xml",
+        }
+    }
+
+    /// Deserialize a value from bytes in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid representation of `T`.
+    pub fn deserialize<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, FormatError> {
+        let _ = bytes;
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "form")]
+            Self::Form => Ok(serde_urlencoded::from_bytes(bytes)?),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => Ok(ciborium::from_reader(bytes)?),
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => Ok(rmp_serde::from_slice(bytes)?),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => Ok(serde_norway::from_slice(bytes)?),
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::from_slice(bytes)?),
+            #[cfg(feature = "xml")]
+            Self::Xml => Ok(quick_xml::de::from_reader(bytes)?),
+        }
+    }
+
+    /// Serialize a value in this format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `T` can't be represented in this format, e.g. a
+    /// list in TOML.
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<Vec<u8>, FormatError> {
+        let _ = value;
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "form")]
+            Self::Form => Ok(serde_urlencoded::to_string(value)?.into_bytes()),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            }
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => Ok(rmp_serde::to_vec_named(value)?),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => Ok(serde_norway::to_string(value)?.into_bytes()),
+            #[cfg(feature = "toml")]
+            Self::Toml => Ok(toml::to_string(value)?.into_bytes()),
+            #[cfg(feature = "xml")]
+            Self::Xml => serialize_xml(value, None, &[]),
+        }
+    }
+
+    /// Rank the enabled response formats by the media types of an accept
+    /// header, which are expected to be sorted already. Wildcards match all
+    /// formats of the type, in the order of [`Format::ALL`]. Media types with
+    /// `q=0` are not acceptable, so their formats are left out, also if a
+    /// wildcard matches them. Form data is only read from requests, so it is
+    /// never ranked.
+    #[must_use]
+    pub fn rank(mediatypes: &[MediaType]) -> Vec<Self> {
+        let rejected: Vec<Self> = mediatypes
+            .iter()
+            .filter(|mt| quality(mt) == 0)
+            .filter_map(Self::from_media_type)
+            .collect();
+        let mut formats: Vec<Self> = Vec::new();
+        for mt in mediatypes.iter().filter(|mt| quality(mt) > 0) {
+            let candidates: Vec<Self> = if mt.subty == _STAR {
+                Self::RESPONSE_FORMATS
+                    .iter()
+                    .copied()
+                    .filter(|format| {
+                        mt.ty == _STAR
+                            || format
+                                .media_type()
+                                .split_once('/')
+                                .is_some_and(|(ty, _)| mt.ty == ty)
+                    })
+                    .collect()
+            } else {
+                Self::from_media_type(mt)
+                    .filter(|format| Self::RESPONSE_FORMATS.contains(format))
+                    .into_iter()
+                    .collect()
+            };
+            for format in candidates {
+                if !formats.contains(&format) && !rejected.contains(&format) {
+                    formats.push(format);
+                }
+            }
+        }
+        formats
+    }
+}
+
+/// How a type is serialized as XML, for the `xml` feature.
+///
+/// With the `xml` feature, [`Negotiated`](crate::Negotiated) serializes XML
+/// representations with the root element and namespaces of the value's type,
+/// so its values have to implement this. Both default, so `impl ToXml for T {}`
+/// names the root element after the type:
+///
+/// ```rust
+/// use axum_accept::{AcceptFormats, Negotiated, ToXml};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// impl ToXml for User {
+///     const ROOT: Option<&'static str> = Some("user");
+///     const NAMESPACES: &'static [(&'static str, &'static str)] = &[("", "urn:example:user")];
+/// }
+///
+/// async fn get_user(formats: AcceptFormats) -> Negotiated<User> {
+///     Negotiated::with_fallbacks(formats, User { name: "Ferris".to_string() })
+/// }
+/// ```
+#[cfg(feature = "xml")]
+pub trait ToXml: Serialize {
+    /// The name of the root element. Defaults to the name of the type.
+    const ROOT: Option<&'static str> = None;
+    /// The namespaces declared on the root element, as prefix and URI. An
+    /// empty prefix declares the default namespace.
+    const NAMESPACES: &'static [(&'static str, &'static str)] = &[];
+}
+
+/// A list has no root element of its own, so it can't be serialized as XML and
+/// [`Negotiated`](crate::Negotiated) falls back to the next representation.
+#[cfg(feature = "xml")]
+impl<T: Serialize> ToXml for Vec<T> {}
+
+#[cfg(feature = "xml")]
+impl<K: Serialize, V: Serialize, H> ToXml for std::collections::HashMap<K, V, H> {}
+
+#[cfg(feature = "xml")]
+impl<K: Serialize, V: Serialize> ToXml for std::collections::BTreeMap<K, V> {}
+
+/// Serialize a value as XML with the root element and namespaces of its type.
+#[cfg(feature = "xml")]
+pub(crate) fn to_xml<T: ToXml>(value: &T) -> Result<Vec<u8>, FormatError> {
+    serialize_xml(value, T::ROOT, T::NAMESPACES)
+}
+
+/// Serialize a value as XML, with the name of its type as the root element
+/// unless `root` is set.
+#[cfg(feature = "xml")]
+fn serialize_xml<T: Serialize>(
+    value: &T,
+    root: Option<&str>,
+    namespaces: &[(&str, &str)],
+) -> Result<Vec<u8>, FormatError> {
+    let mut xml = match root {
+        Some(root) => quick_xml::se::to_string_with_root(root, value)?,
+        None => quick_xml::se::to_string(value)?,
+    };
+    if !namespaces.is_empty() {
+        // the serializer always starts with the root element
+        let end = xml
+            .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .ok_or("Missing XML root element")?;
+        let declarations: String = namespaces
+            .iter()
+            .map(|(prefix, uri)| {
+                let uri = quick_xml::escape::escape(*uri);
+                if prefix.is_empty() {
+                    format!(" xmlns=\"{uri}\"")
+                } else {
+                    format!(" xmlns:{prefix}=\"{uri}\"")
+                }
+            })
+            .collect();
+        xml.insert_str(end, &declarations);
+    }
+    Ok(xml.into_bytes())
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.media_type())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Format;
+    use mediatype::MediaType;
+
+    #[allow(dead_code)] // unused if no format is enabled
+    fn format_of(mt: &str) -> Option<Format> {
+        MediaType::parse(mt)
+            .ok()
+            .and_then(|mt| Format::from_media_type(&mt))
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_format_from_media_type_json() {
+        assert_eq!(Some(Format::Json), format_of("application/json"));
+        assert_eq!(
+            Some(Format::Json),
+            format_of("Application/JSON; charset=utf-8")
+        );
+        assert_eq!(Some(Format::Json), format_of("application/ld+json"));
+        assert_eq!(None, format_of("text/plain"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_format_serialize() -> Result<(), crate::FormatError> {
+        let value = serde_json::json!({ "name": "Ferris" });
+        let bytes = Format::Json.serialize(&value)?;
+        assert_eq!(
+            value,
+            Format::Json.deserialize::<serde_json::Value>(&bytes)?
+        );
+        Ok(())
+    }
+
+    #[allow(dead_code)] // unused if no serde format with a body test is enabled
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct User {
+        name: String,
+        admin: bool,
+    }
+
+    #[allow(dead_code)]
+    fn round_trip(format: Format) -> Result<Vec<u8>, crate::FormatError> {
+        let user = User {
+            name: "Ferris".to_string(),
+            admin: true,
+        };
+        let bytes = format.serialize(&user)?;
+        assert_eq!(user, format.deserialize::<User>(&bytes)?);
+        Ok(bytes)
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_format_msgpack() -> Result<(), crate::FormatError> {
+        let bytes = round_trip(Format::MsgPack)?;
+        // a map with named fields
+        assert_eq!(Some(&0x82), bytes.first());
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_format_yaml() -> Result<(), crate::FormatError> {
+        let bytes = round_trip(Format::Yaml)?;
+        assert_eq!("name: Ferris\nadmin: true\n", String::from_utf8(bytes)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_format_toml() -> Result<(), crate::FormatError> {
+        let bytes = round_trip(Format::Toml)?;
+        assert_eq!(
+            "name = \"Ferris\"\nadmin = true\n",
+            String::from_utf8(bytes)?
+        );
+        assert!(Format::Toml.serialize(&vec![1, 2, 3]).is_err());
+        Ok(())
+    }
+
+    #[cfg(all(feature = "json", feature = "cbor"))]
+    #[test]
+    fn test_format_rank() -> Result<(), Box<dyn std::error::Error>> {
+        let mediatypes = [
+            MediaType::parse("application/cbor")?,
+            MediaType::parse("application/*")?,
+        ];
+        let formats = Format::rank(&mediatypes);
+        assert_eq!(Some(&Format::Cbor), formats.first());
+        assert_eq!(Some(&Format::Json), formats.get(1));
+        assert_eq!(Format::RESPONSE_FORMATS.len(), formats.len());
+
+        let mediatypes = [MediaType::parse("text/*")?];
+        assert!(Format::rank(&mediatypes).is_empty());
+
+        // q=0 is not acceptable, also not through a wildcard
+        let mediatypes = [MediaType::parse("application/json;q=0")?];
+        assert!(Format::rank(&mediatypes).is_empty());
+        let mediatypes = [
+            MediaType::parse("*/*")?,
+            MediaType::parse("application/json;q=0")?,
+        ];
+        assert!(!Format::rank(&mediatypes).contains(&Format::Json));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "json", feature = "form"))]
+    #[test]
+    fn test_format_rank_form() -> Result<(), Box<dyn std::error::Error>> {
+        // form data is only read from requests, never responded with
+        let mediatypes = [MediaType::parse("application/*")?];
+        assert!(!Format::rank(&mediatypes).contains(&Format::Form));
+        let mediatypes = [MediaType::parse("application/x-www-form-urlencoded")?];
+        assert!(Format::rank(&mediatypes).is_empty());
+        Ok(())
+    }
+
+    #[cfg(all(
+        feature = "cbor",
+        feature = "msgpack",
+        feature = "yaml",
+        feature = "form"
+    ))]
+    #[test]
+    fn test_format_from_media_type_others() {
+        assert_eq!(
+            Some(Format::Form),
+            format_of("application/x-www-form-urlencoded")
+        );
+        assert_eq!(Some(Format::Cbor), format_of("application/cbor"));
+        assert_eq!(Some(Format::Cbor), format_of("application/senml+cbor"));
+        assert_eq!(Some(Format::MsgPack), format_of("application/x-msgpack"));
+        assert_eq!(Some(Format::Yaml), format_of("text/yaml"));
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_format_xml() -> Result<(), crate::FormatError> {
+        use super::{ToXml, to_xml};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct User {
+            name: String,
+        }
+
+        impl ToXml for User {
+            const ROOT: Option<&'static str> = Some("user");
+            const NAMESPACES: &'static [(&'static str, &'static str)] = &[
+                ("", "urn:example:user"),
+                ("xlink", "http://www.w3.org/1999/xlink"),
+            ];
+        }
+
+        assert_eq!(Some(Format::Xml), format_of("text/xml"));
+        assert_eq!(Some(Format::Xml), format_of("application/atom+xml"));
+
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        let bytes = Format::Xml.serialize(&user)?;
+        assert_eq!(b"<User><name>Ferris</name></User>", bytes.as_slice());
+        assert_eq!(user, Format::Xml.deserialize::<User>(&bytes)?);
+
+        let bytes = to_xml(&user)?;
+        assert_eq!(
+            r#"<user xmlns="urn:example:user" xmlns:xlink="http://www.w3.org/1999/xlink"><name>Ferris</name></user>"#,
+            String::from_utf8(bytes)?
+        );
+
+        // a list has no root element of its own
+        assert!(Format::Xml.serialize(&vec![1, 2, 3]).is_err());
+        assert!(to_xml(&vec![1, 2, 3]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+//! Rendering values as HTML for `text/html` representations.
+use crate::format::FormatError;
+
+/// A value that can be rendered as an HTML page, e.g. with a template.
+///
+/// Combined with [`Negotiated::html`](crate::Negotiated::html), a `text/html`
+/// representation renders the same value the other representations serialize.
+/// With the `askama` feature, this is implemented for all askama templates.
+/// Other engines like maud only need the method:
+///
+/// ```rust
+/// use axum_accept::{FormatError, RenderHtml};
+///
+/// struct Counter {
+///     count: u32,
+/// }
+///
+/// impl RenderHtml for Counter {
+///     fn render_html(&self) -> Result<String, FormatError> {
+///         // e.g. `Ok(maud::html! { p { (self.count) } }.into_string())`
+///         Ok(format!("<p>{}</p>", self.count))
+///     }
+/// }
+/// ```
+pub trait RenderHtml {
+    /// Render the value as HTML.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value could not be rendered.
+    fn render_html(&self) -> Result<String, FormatError>;
+}
+
+/// The [`RenderHtml::render_html`] of a type.
+pub(crate) type RenderFn<T> = fn(&T) -> Result<String, FormatError>;
+
+#[cfg(feature = "askama")]
+impl<T> RenderHtml for T
+where
+    T: askama::Template,
+{
+    fn render_html(&self) -> Result<String, FormatError> {
+        Ok(self.render()?)
+    }
+}
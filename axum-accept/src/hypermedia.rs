@@ -0,0 +1,392 @@
+//! Hypermedia representations: HAL, JSON:API and Siren.
+use axum::http::HeaderValue;
+use mediatype::MediaType;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    format::{Format, FormatError},
+    negotiated::Representation,
+};
+
+/// A link of a [`Resource`] to a related resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The relation type, e.g. `self` or `next`.
+    pub rel: String,
+    /// The target of the link.
+    pub href: String,
+    /// A human-readable title of the target, if any.
+    pub title: Option<String>,
+}
+
+impl Link {
+    /// Create a link without a title.
+    #[must_use]
+    pub fn new(rel: &str, href: &str) -> Self {
+        Self {
+            rel: rel.to_string(),
+            href: href.to_string(),
+            title: None,
+        }
+    }
+
+    /// Set the title of the link.
+    #[must_use]
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+}
+
+/// The links of a [`Resource`], declared once and rendered by each
+/// [`Hypermedia`] format in its own structure.
+///
+/// A relation can have several links. HAL renders them as an array and Siren
+/// as separate links, but JSON:API allows only one link per relation, so
+/// [`Hypermedia::JsonApi`] renders the first one and leaves out the others.
+///
+/// ```rust
+/// use axum_accept::{Link, Links};
+///
+/// let links = Links::new()
+///     .link("self", "/users/1")
+///     .with(Link::new("friends", "/users/1/friends").title("Friends"));
+/// assert_eq!(2, links.iter().count());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Links(Vec<Link>);
+
+impl Links {
+    /// Create empty links.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a link without a title.
+    #[must_use]
+    pub fn link(self, rel: &str, href: &str) -> Self {
+        self.with(Link::new(rel, href))
+    }
+
+    /// Add a link.
+    #[must_use]
+    pub fn with(mut self, link: Link) -> Self {
+        self.0.push(link);
+        self
+    }
+
+    /// Iterate over the links, in the order they were added.
+    pub fn iter(&self) -> std::slice::Iter<'_, Link> {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Links {
+    type Item = &'a Link;
+    type IntoIter = std::slice::Iter<'a, Link>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A resource that can be rendered in the [`Hypermedia`] formats.
+///
+/// The fields of the serialized resource become its attributes, so it has to
+/// serialize to a JSON object.
+pub trait Resource {
+    /// The type of the resource, the `type` in JSON:API and the `class` in
+    /// Siren.
+    const TYPE: &'static str;
+
+    /// The id of the resource for JSON:API. By default, this is taken from
+    /// the `id` field of the serialized resource.
+    fn id(&self) -> Option<String> {
+        None
+    }
+
+    /// The links of the resource.
+    fn links(&self) -> Links;
+}
+
+/// A hypermedia format, which renders a [`Resource`] with its [`Links`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hypermedia {
+    /// `application/hal+json`, with the links in `_links`.
+    Hal,
+    /// `application/vnd.api+json`, with the resource in `data`. Only the
+    /// first link of each relation is rendered, as JSON:API allows one.
+    JsonApi,
+    /// `application/vnd.siren+json`, with the fields in `properties`.
+    Siren,
+}
+
+impl Hypermedia {
+    /// Get the hypermedia format of a media type, if it is one.
+    #[must_use]
+    pub fn from_media_type(mt: &MediaType) -> Option<Self> {
+        let is_json = mt.suffix.is_some_and(|suffix| suffix == "json");
+        if mt.ty != "application" || !is_json {
+            return None;
+        }
+        if mt.subty == "hal" {
+            Some(Self::Hal)
+        } else if mt.subty == "vnd.api" {
+            Some(Self::JsonApi)
+        } else if mt.subty == "vnd.siren" {
+            Some(Self::Siren)
+        } else {
+            None
+        }
+    }
+
+    /// Get the hypermedia format of a negotiated representation, e.g. a
+    /// variant of a derived `AcceptExtractor`.
+    pub fn from_representation(representation: &impl Representation) -> Option<Self> {
+        let content_type = representation.content_type();
+        let mt = MediaType::parse(content_type.to_str().ok()?).ok()?;
+        Self::from_media_type(&mt)
+    }
+
+    /// The media type of the hypermedia format.
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Hal => "application/hal+json",
+            Self::JsonApi => "application/vnd.api+json",
+            Self::Siren => "application/vnd.siren+json",
+        }
+    }
+
+    /// Render a resource in this format. With JSON:API, only the first link
+    /// of each relation is rendered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the resource doesn't serialize to a JSON object.
+    pub fn render<T>(self, resource: &T) -> Result<Value, FormatError>
+    where
+        T: Resource + Serialize,
+    {
+        let Value::Object(mut attributes) = serde_json::to_value(resource)? else {
+            return Err(format!("The {} resource is no JSON object", T::TYPE).into());
+        };
+        let links = resource.links();
+        Ok(match self {
+            Self::Hal => {
+                let mut hal_links = Map::new();
+                for link in &links {
+                    let mut object = json!({ "href": link.href });
+                    if let Some(title) = &link.title {
+                        object["title"] = json!(title);
+                    }
+                    // several links of the same relation become an array
+                    match hal_links.get_mut(&link.rel) {
+                        Some(Value::Array(array)) => array.push(object),
+                        Some(existing) => *existing = json!([existing.take(), object]),
+                        None => {
+                            hal_links.insert(link.rel.clone(), object);
+                        }
+                    }
+                }
+                if !hal_links.is_empty() {
+                    attributes.insert("_links".to_string(), Value::Object(hal_links));
+                }
+                Value::Object(attributes)
+            }
+            Self::JsonApi => {
+                // JSON:API doesn't allow `id` as an attribute
+                let field_id = attributes.remove("id").map(|id| match id {
+                    Value::String(id) => id,
+                    id => id.to_string(),
+                });
+                let id = resource.id().or(field_id);
+                let mut api_links = Map::new();
+                for link in &links {
+                    // JSON:API allows only one link per relation, the first one wins
+                    if api_links.contains_key(&link.rel) {
+                        continue;
+                    }
+                    let object = match &link.title {
+                        Some(title) => json!({ "href": link.href, "title": title }),
+                        None => json!(link.href),
+                    };
+                    api_links.insert(link.rel.clone(), object);
+                }
+                let mut data = json!({ "type": T::TYPE, "attributes": attributes });
+                if let Some(id) = id {
+                    data["id"] = json!(id);
+                }
+                if !api_links.is_empty() {
+                    data["links"] = Value::Object(api_links);
+                }
+                json!({ "data": data })
+            }
+            Self::Siren => {
+                let siren_links: Vec<Value> = links
+                    .iter()
+                    .map(|link| {
+                        let mut object = json!({ "rel": [link.rel], "href": link.href });
+                        if let Some(title) = &link.title {
+                            object["title"] = json!(title);
+                        }
+                        object
+                    })
+                    .collect();
+                json!({
+                    "class": [T::TYPE],
+                    "properties": attributes,
+                    "links": siren_links,
+                })
+            }
+        })
+    }
+}
+
+impl Representation for Hypermedia {
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(self.media_type())
+    }
+
+    fn format(&self) -> Option<Format> {
+        None
+    }
+}
+
+/// The [`Hypermedia::render`] of a type, for `Negotiated`.
+pub(crate) type HypermediaFn<T> = fn(&T, Hypermedia) -> Result<Vec<u8>, FormatError>;
+
+/// Render and serialize a resource in a hypermedia format.
+pub(crate) fn render_hypermedia<T>(
+    resource: &T,
+    hypermedia: Hypermedia,
+) -> Result<Vec<u8>, FormatError>
+where
+    T: Resource + Serialize,
+{
+    Ok(serde_json::to_vec(&hypermedia.render(resource)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hypermedia, Link, Links, Resource};
+    use crate::FormatError;
+    use crate::{AcceptExtractor, Negotiated};
+    use axum::{body::to_bytes, http::header, response::IntoResponse};
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    #[cfg(feature = "xml")]
+    impl crate::ToXml for User {}
+
+    impl Resource for User {
+        const TYPE: &'static str = "user";
+
+        fn links(&self) -> Links {
+            Links::new()
+                .link("self", &format!("/users/{}", self.id))
+                .with(Link::new("friends", "/users/1/friends").title("Friends"))
+                .link("friends", "/users/1/followers")
+        }
+    }
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "application/hal+json")]
+        Hal,
+        #[accept(mediatype = "application/json")]
+        Json,
+    }
+
+    fn user() -> User {
+        User {
+            id: 1,
+            name: "Ferris".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hal() -> Result<(), FormatError> {
+        assert_eq!(
+            json!({
+                "id": 1,
+                "name": "Ferris",
+                "_links": {
+                    "self": { "href": "/users/1" },
+                    "friends": [
+                        { "href": "/users/1/friends", "title": "Friends" },
+                        { "href": "/users/1/followers" },
+                    ],
+                },
+            }),
+            Hypermedia::Hal.render(&user())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_api() -> Result<(), FormatError> {
+        // the second `friends` link is left out
+        assert_eq!(
+            json!({
+                "data": {
+                    "type": "user",
+                    "id": "1",
+                    "attributes": { "name": "Ferris" },
+                    "links": {
+                        "self": "/users/1",
+                        "friends": { "href": "/users/1/friends", "title": "Friends" },
+                    },
+                },
+            }),
+            Hypermedia::JsonApi.render(&user())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_siren() -> Result<(), FormatError> {
+        assert_eq!(
+            json!({
+                "class": ["user"],
+                "properties": { "id": 1, "name": "Ferris" },
+                "links": [
+                    { "rel": ["self"], "href": "/users/1" },
+                    { "rel": ["friends"], "href": "/users/1/friends", "title": "Friends" },
+                    { "rel": ["friends"], "href": "/users/1/followers" },
+                ],
+            }),
+            Hypermedia::Siren.render(&user())?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_hypermedia() -> Result<(), Box<dyn std::error::Error>> {
+        let response = Negotiated::new(Accept::Hal, user())
+            .hypermedia()
+            .into_response();
+        assert_eq!(
+            "application/hal+json",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let value: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(json!("/users/1"), value["_links"]["self"]["href"]);
+
+        let response = Negotiated::new(Accept::Json, user())
+            .hypermedia()
+            .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(r#"{"id":1,"name":"Ferris"}"#, body);
+        Ok(())
+    }
+}
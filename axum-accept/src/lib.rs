@@ -11,14 +11,80 @@
 //!     TextPlain,
 //! }
 //! ```
+//!
+//! The same works for the `Content-Type` of request bodies:
+//!
+//! ```rust
+//! use axum_accept::ContentTypeExtractor;
+//!
+//! #[derive(ContentTypeExtractor)]
+//! enum ContentType {
+//!     #[content_type(mediatype="application/json")]
+//!     ApplicationJson,
+//! }
+//! ```
 #![deny(warnings)]
 #![deny(clippy::pedantic, clippy::unwrap_used)]
 #![deny(missing_docs)]
-pub use axum_accept_macros::AcceptExtractor;
-pub use axum_accept_shared::AcceptRejection;
+// the derives refer to `::axum_accept`, also in this crate
+extern crate self as axum_accept;
+
+mod body;
+#[cfg(feature = "csv")]
+mod csv;
+mod format;
+mod html;
+#[cfg(feature = "json")]
+mod hypermedia;
+mod negotiated;
+#[cfg(feature = "patch")]
+mod patch;
+mod prefer;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod sse;
+#[cfg(feature = "stream")]
+mod stream;
+mod vary;
 
+#[cfg(feature = "csv")]
+pub use crate::csv::{AcceptCsv, Csv};
+pub use axum_accept_macros::{AcceptExtractor, ContentTypeExtractor, Negotiate, PreferExtractor};
+pub use axum_accept_shared::{
+    ACCEPT_PATCH, ACCEPT_POST, AcceptRejection, ContentTypeRejection, Dimension,
+    DimensionRejection, NegotiateRejection, PREFER, PREFERENCE_APPLIED, PreferRejection,
+    Preference, RejectionBody,
+};
+pub use body::{NegotiatedBody, NegotiatedBodyRejection};
+#[cfg(feature = "xml")]
+pub use format::ToXml;
+pub use format::{Format, FormatError};
+pub use html::RenderHtml;
+#[cfg(feature = "json")]
+pub use hypermedia::{Hypermedia, Link, Links, Resource};
+pub use negotiated::{AcceptFormats, Negotiated, Representation};
+#[cfg(feature = "patch")]
+pub use patch::{Patch, PatchDocument, PatchError};
+pub use prefer::{Prefer, PreferenceApplied};
+#[cfg(feature = "protobuf")]
+pub use protobuf::{Protobuf, ProtobufRejection};
+pub use sse::{LiveOrSnapshot, is_event_stream};
+#[cfg(feature = "stream")]
+pub use stream::{JsonStream, JsonStreamFormat};
+pub use vary::{VaryLayer, VaryService, record_vary};
+
+#[doc(hidden)]
+pub use axum_accept_shared::{
+    params_match, parse_content_type, parse_mediatypes, parse_mediatypes_lenient,
+    parse_preferences, quality, supported_media_types_header, vary_header_value,
+};
+
+/// Re-exports for the generated code, so it doesn't depend on the names of the
+/// dependencies of the user.
 #[doc(hidden)]
-pub use axum_accept_shared::parse_mediatypes;
+pub mod __private {
+    pub use axum;
+}
 
 #[cfg(doctest)]
 #[doc = include_str!("../../README.md")]
@@ -27,10 +93,11 @@ pub struct ReadmeDoctests;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate as axum_accept; // necessary for the macro to work
     use axum::{
         body::Body,
         extract::{FromRequest, Request},
+        http::{Method, StatusCode},
+        response::IntoResponse,
     };
 
     #[derive(Debug, AcceptExtractor)]
@@ -43,6 +110,34 @@ mod tests {
         ApplicationLdJson,
     }
 
+    #[derive(Debug, AcceptExtractor)]
+    enum AcceptWithParams {
+        #[accept(mediatype = "text/plain;charset=utf-8")]
+        TextPlain,
+        #[accept(
+            mediatype = "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\""
+        )]
+        ActivityStreams,
+    }
+
+    #[test]
+    fn test_accept_extractor_content_type() {
+        assert_eq!(
+            "application/ld+json",
+            Accept::ApplicationLdJson.content_type()
+        );
+        assert_eq!(
+            "text/plain; charset=utf-8",
+            AcceptWithParams::TextPlain.content_type()
+        );
+
+        let response = (AcceptWithParams::ActivityStreams, "{}").into_response();
+        assert_eq!(
+            "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"",
+            response.headers()["content-type"]
+        );
+    }
+
     #[tokio::test]
     async fn test_accept_extractor_basic() -> Result<(), Box<dyn std::error::Error>> {
         let req = Request::builder()
@@ -110,9 +205,54 @@ mod tests {
             .body(Body::from(""))?;
         let state = ();
         let media_type = Accept::from_request(req, &state).await;
-        let Err(AcceptRejection::NoSupportedMediaTypeFound) = media_type else {
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { offered, body, .. }) = media_type
+        else {
             panic!("expected no supported media type found")
         };
+        assert_eq!(
+            &["text/plain", "application/json", "application/ld+json"],
+            offered
+        );
+        assert_eq!(RejectionBody::Plain, body);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "problem-json"))]
+    #[tokio::test]
+    async fn test_accept_extractor_no_match_body() -> Result<(), Box<dyn std::error::Error>> {
+        use axum::body::to_bytes;
+
+        let req = Request::builder()
+            .header("accept", "application/vnd.example+json")
+            .body(Body::from(""))?;
+        let Err(rejection) = Accept::from_request(req, &()).await else {
+            panic!("expected a rejection")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, response.status());
+        assert_eq!("application/json", response.headers()["content-type"]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(
+            serde_json::json!(["text/plain", "application/json", "application/ld+json"]),
+            body["offered"]
+        );
+
+        let req = Request::builder()
+            .header("accept", "text/html, image/webp;q=0.9")
+            .body(Body::from(""))?;
+        let Err(rejection) = Accept::from_request(req, &()).await else {
+            panic!("expected a rejection")
+        };
+        let response = rejection.into_response();
+        assert_eq!(
+            "text/html; charset=utf-8",
+            response.headers()["content-type"]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert!(
+            String::from_utf8(body.to_vec())?.contains("<li><code>application/ld+json</code></li>")
+        );
         Ok(())
     }
 
@@ -124,7 +264,170 @@ mod tests {
         let state = ();
         let media_type = Accept::from_request(req, &state).await;
         let Ok(Accept::TextPlain) = media_type else {
-            panic!("expected text/*, got {:?}", media_type)
+            panic!("expected text/*, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    enum AcceptText {
+        #[accept(mediatype = "text/plain")]
+        TextPlain,
+        #[accept(mediatype = "text/html")]
+        TextHtml,
+        #[accept(mediatype = "text/csv")]
+        TextCsv,
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    enum AcceptWithPriority {
+        #[accept(mediatype = "text/plain", priority = -1)]
+        TextPlain,
+        #[accept(mediatype = "text/html", priority = 1)]
+        TextHtml,
+        #[accept(mediatype = "application/json", priority = 2)]
+        ApplicationJson,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_star_first() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptText::from_request(req, &()).await;
+        let Ok(AcceptText::TextPlain) = media_type else {
+            panic!("expected text/plain (first), got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_q_zero() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json;q=0")
+            .body(Body::from(""))?;
+        let media_type = Accept::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = media_type else {
+            panic!("expected no supported media type found, got {media_type:?}")
+        };
+
+        // a variant excluded with q=0 is not chosen for wildcards either
+        let req = Request::builder()
+            .header("accept", "text/plain;q=0,*/*;q=0.1")
+            .body(Body::from(""))?;
+        let media_type = AcceptText::from_request(req, &()).await;
+        let Ok(AcceptText::TextHtml) = media_type else {
+            panic!("expected text/html (first not excluded), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/html;q=0,text/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextPlain) = media_type else {
+            panic!("expected text/plain (html excluded), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "application/json;q=0,*/*;q=0.5")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextHtml) = media_type else {
+            panic!("expected text/html (json excluded), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/plain;q=0,text/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptText::from_request(req, &()).await;
+        let Ok(AcceptText::TextHtml) = media_type else {
+            panic!("expected text/html (plain excluded), got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_case_insensitive() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "Application/JSON")
+            .body(Body::from(""))?;
+        let media_type = Accept::from_request(req, &()).await;
+        let Ok(Accept::ApplicationJson) = media_type else {
+            panic!("expected application/json, got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "APPLICATION/LD+JSON")
+            .body(Body::from(""))?;
+        let media_type = Accept::from_request(req, &()).await;
+        let Ok(Accept::ApplicationLdJson) = media_type else {
+            panic!("expected application/ld+json, got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "TEXT/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptText::from_request(req, &()).await;
+        let Ok(AcceptText::TextPlain) = media_type else {
+            panic!("expected text/plain, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_priority() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextHtml) = media_type else {
+            panic!("expected text/html (priority), got {media_type:?}")
+        };
+
+        // ties between ranges with the same q
+        let req = Request::builder()
+            .header("accept", "text/plain,text/html")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextHtml) = media_type else {
+            panic!("expected text/html (priority), got {media_type:?}")
+        };
+
+        // an explicitly named type wins over wildcards, whatever their
+        // priority
+        let req = Request::builder()
+            .header("accept", "text/plain,*/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextPlain) = media_type else {
+            panic!("expected text/plain (explicit), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/plain,text/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextPlain) = media_type else {
+            panic!("expected text/plain (explicit), got {media_type:?}")
+        };
+
+        // q still wins over priority
+        let req = Request::builder()
+            .header("accept", "text/plain,application/json;q=0.9")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::TextPlain) = media_type else {
+            panic!("expected text/plain (q), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "*/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithPriority::from_request(req, &()).await;
+        let Ok(AcceptWithPriority::ApplicationJson) = media_type else {
+            panic!("expected application/json (priority), got {media_type:?}")
         };
         Ok(())
     }
@@ -142,6 +445,48 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Debug)]
+    struct ApiError(StatusCode);
+
+    impl From<AcceptRejection> for ApiError {
+        fn from(rejection: AcceptRejection) -> Self {
+            Self(rejection.status_and_message().0)
+        }
+    }
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> axum::response::Response {
+            (self.0, "api error").into_response()
+        }
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    #[accept(rejection = ApiError)]
+    enum AcceptWithRejection {
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_rejection() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let result = AcceptWithRejection::from_request(req, &()).await;
+        let Err(ApiError(StatusCode::NOT_ACCEPTABLE)) = result else {
+            panic!("expected a not acceptable api error, got {result:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/csv;q=lol")
+            .body(Body::from(""))?;
+        let result = AcceptWithRejection::from_request(req, &()).await;
+        let Err(ApiError(StatusCode::BAD_REQUEST)) = result else {
+            panic!("expected a bad request api error, got {result:?}")
+        };
+        Ok(())
+    }
+
     #[derive(Debug, AcceptExtractor, Default)]
     enum AcceptWithDefault {
         #[accept(mediatype = "application/json")]
@@ -158,7 +503,7 @@ mod tests {
             .body(Body::from(""))?;
         let state = ();
         let media_type = AcceptWithDefault::from_request(req, &state).await;
-        let Err(AcceptRejection::NoSupportedMediaTypeFound) = media_type else {
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = media_type else {
             panic!("expected no supported media type found")
         };
 
@@ -183,4 +528,521 @@ mod tests {
         };
         Ok(())
     }
+
+    #[derive(Debug, AcceptExtractor)]
+    #[accept(on_missing = ApplicationJson, on_any = TextHtml)]
+    enum AcceptWithFallbacks {
+        #[accept(mediatype = "text/plain")]
+        TextPlain,
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+        #[accept(mediatype = "text/html")]
+        TextHtml,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_on_missing_and_any() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder().body(Body::from(""))?;
+        let media_type = AcceptWithFallbacks::from_request(req, &()).await;
+        let Ok(AcceptWithFallbacks::ApplicationJson) = media_type else {
+            panic!("expected application/json (on_missing), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "image/png,*/*;q=0.8")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithFallbacks::from_request(req, &()).await;
+        let Ok(AcceptWithFallbacks::TextHtml) = media_type else {
+            panic!("expected text/html (on_any), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithFallbacks::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = media_type else {
+            panic!("expected no supported media type found, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug, AcceptExtractor, Default)]
+    #[accept(on_unacceptable = "default")]
+    enum AcceptOrDefault {
+        #[accept(mediatype = "text/plain")]
+        TextPlain,
+        #[default]
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    #[accept(on_unacceptable = "first")]
+    enum AcceptOrFirst {
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+        #[accept(mediatype = "text/plain")]
+        TextPlain,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_on_unacceptable() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let media_type = AcceptOrDefault::from_request(req, &()).await;
+        let Ok(AcceptOrDefault::ApplicationJson) = media_type else {
+            panic!("expected application/json (default), got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let media_type = AcceptOrFirst::from_request(req, &()).await;
+        let Ok(AcceptOrFirst::ApplicationJson) = media_type else {
+            panic!("expected application/json (first), got {media_type:?}")
+        };
+
+        // invalid headers are ignored as well
+        let req = Request::builder()
+            .header("accept", "text/plain;q=lol")
+            .body(Body::from(""))?;
+        let media_type = AcceptOrFirst::from_request(req, &()).await;
+        let Ok(AcceptOrFirst::ApplicationJson) = media_type else {
+            panic!("expected application/json (first), got {media_type:?}")
+        };
+
+        // acceptable variants are still negotiated
+        let req = Request::builder()
+            .header("accept", "text/plain")
+            .body(Body::from(""))?;
+        let media_type = AcceptOrDefault::from_request(req, &()).await;
+        let Ok(AcceptOrDefault::TextPlain) = media_type else {
+            panic!("expected text/plain, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug, AcceptExtractor, Default)]
+    enum AcceptWithCfg {
+        #[cfg_attr(not(feature = "yaml"), default)]
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+        #[cfg(feature = "yaml")]
+        #[cfg_attr(feature = "yaml", default)]
+        #[accept(mediatype = "application/yaml")]
+        ApplicationYaml,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_cfg() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/yaml")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithCfg::from_request(req, &()).await;
+        #[cfg(feature = "yaml")]
+        let Ok(AcceptWithCfg::ApplicationYaml) = media_type else {
+            panic!("expected application/yaml, got {media_type:?}")
+        };
+        #[cfg(not(feature = "yaml"))]
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = media_type else {
+            panic!("expected no supported media type found, got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "*/*")
+            .body(Body::from(""))?;
+        let media_type = AcceptWithCfg::from_request(req, &()).await;
+        #[cfg(feature = "yaml")]
+        let Ok(AcceptWithCfg::ApplicationYaml) = media_type else {
+            panic!("expected application/yaml (default), got {media_type:?}")
+        };
+        #[cfg(not(feature = "yaml"))]
+        let Ok(AcceptWithCfg::ApplicationJson) = media_type else {
+            panic!("expected application/json (default), got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    /// A facade re-exporting axum-accept under another name.
+    mod facade {
+        pub use crate as accept;
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    #[accept(crate = "self::facade::accept")]
+    enum AcceptFromFacade {
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_crate() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json")
+            .body(Body::from(""))?;
+        let media_type = AcceptFromFacade::from_request(req, &()).await;
+        let Ok(AcceptFromFacade::ApplicationJson) = media_type else {
+            panic!("expected application/json, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug, AcceptExtractor)]
+    #[accept(lenient)]
+    enum AcceptLenient {
+        #[accept(mediatype = "text/html")]
+        TextHtml,
+        #[accept(mediatype = "application/json")]
+        ApplicationJson,
+    }
+
+    #[tokio::test]
+    async fn test_accept_extractor_lenient() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/html;q=,lol,application/json;q=0.9")
+            .body(Body::from(""))?;
+        let media_type = AcceptLenient::from_request(req, &()).await;
+        let Ok(AcceptLenient::ApplicationJson) = media_type else {
+            panic!("expected application/json, got {media_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/html;q=")
+            .body(Body::from(""))?;
+        let media_type = AcceptLenient::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = media_type else {
+            panic!("expected no supported media type found, got {media_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug, ContentTypeExtractor)]
+    enum ContentType {
+        #[content_type(mediatype = "application/json")]
+        ApplicationJson,
+        #[content_type(mediatype = "application/ld+json")]
+        ApplicationLdJson,
+        #[content_type(mediatype = "text/plain; charset=utf-8")]
+        TextPlainUtf8,
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_basic() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(""))?;
+        let state = ();
+        let content_type = ContentType::from_request(req, &state).await;
+        let Ok(ContentType::ApplicationJson) = content_type else {
+            panic!("expected application/json, got {content_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_case_insensitive() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let req = Request::builder()
+            .header("content-type", "Application/JSON")
+            .body(Body::from(""))?;
+        let content_type = ContentType::from_request(req, &()).await;
+        let Ok(ContentType::ApplicationJson) = content_type else {
+            panic!("expected application/json, got {content_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("content-type", "TEXT/PLAIN; Charset=UTF-8")
+            .body(Body::from(""))?;
+        let content_type = ContentType::from_request(req, &()).await;
+        let Ok(ContentType::TextPlainUtf8) = content_type else {
+            panic!("expected text/plain; charset=utf-8, got {content_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_suffix() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "application/ld+json")
+            .body(Body::from(""))?;
+        let state = ();
+        let content_type = ContentType::from_request(req, &state).await;
+        let Ok(ContentType::ApplicationLdJson) = content_type else {
+            panic!("expected application/ld+json, got {content_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_params() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("content-type", "text/plain; charset=UTF-8")
+            .body(Body::from(""))?;
+        let state = ();
+        let content_type = ContentType::from_request(req, &state).await;
+        let Ok(ContentType::TextPlainUtf8) = content_type else {
+            panic!("expected text/plain; charset=utf-8, got {content_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("content-type", "text/plain; charset=latin1")
+            .body(Body::from(""))?;
+        let content_type = ContentType::from_request(req, &state).await;
+        let Err(ContentTypeRejection::UnsupportedMediaType(..)) = content_type else {
+            panic!("expected unsupported media type, got {content_type:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_unsupported() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .method(Method::POST)
+            .header("content-type", "text/csv")
+            .body(Body::from(""))?;
+        let state = ();
+        let Err(rejection) = ContentType::from_request(req, &state).await else {
+            panic!("expected unsupported media type")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert_eq!(
+            "application/json, application/ld+json, text/plain; charset=utf-8",
+            response.headers()[ACCEPT_POST]
+        );
+
+        let req = Request::builder()
+            .method(Method::PATCH)
+            .body(Body::from(""))?;
+        let Err(rejection) = ContentType::from_request(req, &state).await else {
+            panic!("expected unsupported media type")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert!(response.headers().contains_key(ACCEPT_PATCH));
+        Ok(())
+    }
+
+    #[derive(Debug, ContentTypeExtractor, Default)]
+    enum ContentTypeWithDefault {
+        #[default]
+        #[content_type(mediatype = "application/json")]
+        ApplicationJson,
+        #[content_type(mediatype = "text/plain")]
+        TextPlain,
+    }
+
+    #[tokio::test]
+    async fn test_content_type_extractor_default() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder().body(Body::from(""))?;
+        let state = ();
+        let content_type = ContentTypeWithDefault::from_request(req, &state).await;
+        let Ok(ContentTypeWithDefault::ApplicationJson) = content_type else {
+            panic!("expected application/json (default), got {content_type:?}")
+        };
+
+        let req = Request::builder()
+            .header("content-type", "text/plain")
+            .body(Body::from(""))?;
+        let content_type = ContentTypeWithDefault::from_request(req, &state).await;
+        let Ok(ContentTypeWithDefault::TextPlain) = content_type else {
+            panic!("expected text/plain, got {content_type:?}")
+        };
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct Lang(String);
+
+    impl<S: Send + Sync> axum::extract::FromRequestParts<S> for Lang {
+        type Rejection = AcceptRejection;
+
+        async fn from_request_parts(
+            parts: &mut axum::http::request::Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            match parts.headers.get("accept-language") {
+                Some(lang) if lang == "en" || lang == "de" => Ok(Lang(
+                    lang.to_str()
+                        .map_err(AcceptRejection::InvalidHeader)?
+                        .to_string(),
+                )),
+                _ => Err(AcceptRejection::not_acceptable(
+                    &parts.headers,
+                    &["en", "de"],
+                )),
+            }
+        }
+    }
+
+    impl Dimension for Lang {
+        const HEADER: axum::http::HeaderName = axum::http::header::ACCEPT_LANGUAGE;
+    }
+
+    #[derive(Debug, Negotiate)]
+    struct Repr {
+        format: Accept,
+        lang: Lang,
+    }
+
+    #[tokio::test]
+    async fn test_negotiate() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json")
+            .header("accept-language", "de")
+            .body(Body::from(""))?;
+        let state = ();
+        let repr = Repr::from_request(req, &state).await;
+        let Ok(Repr {
+            format: Accept::ApplicationJson,
+            lang: Lang(lang),
+        }) = repr
+        else {
+            panic!("expected application/json and a language, got {repr:?}")
+        };
+        assert_eq!("de", lang);
+        assert_eq!("accept, accept-language", Repr::vary());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_rejection() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json")
+            .header("accept-language", "fr")
+            .body(Body::from(""))?;
+        let state = ();
+        let Err(rejection) = Repr::from_request(req, &state).await else {
+            panic!("expected rejection")
+        };
+        assert_eq!(1, rejection.failures.len());
+        assert_eq!("lang", rejection.failures[0].field);
+        assert_eq!(
+            StatusCode::NOT_ACCEPTABLE,
+            rejection.into_response().status()
+        );
+
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let Err(rejection) = Repr::from_request(req, &state).await else {
+            panic!("expected rejection")
+        };
+        let fields: Vec<_> = rejection.failures.iter().map(|f| f.field).collect();
+        assert_eq!(vec!["format", "lang"], fields);
+        Ok(())
+    }
+
+    #[derive(Debug, Negotiate)]
+    struct ApiRepr {
+        format: AcceptWithRejection,
+        lang: Lang,
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_custom_rejection() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "application/json")
+            .header("accept-language", "de")
+            .body(Body::from(""))?;
+        let repr = ApiRepr::from_request(req, &()).await;
+        let Ok(ApiRepr {
+            format: AcceptWithRejection::ApplicationJson,
+            lang: Lang(lang),
+        }) = repr
+        else {
+            panic!("expected application/json and a language, got {repr:?}")
+        };
+        assert_eq!("de", lang);
+
+        // ApiError doesn't implement Display
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .header("accept-language", "de")
+            .body(Body::from(""))?;
+        let Err(rejection) = ApiRepr::from_request(req, &()).await else {
+            panic!("expected rejection")
+        };
+        assert_eq!(1, rejection.failures.len());
+        assert_eq!("api error", rejection.failures[0].message());
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::NOT_ACCEPTABLE, response.status());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("api error", body);
+
+        let req = Request::builder()
+            .header("accept", "text/csv")
+            .header("accept-language", "fr")
+            .body(Body::from(""))?;
+        let Err(rejection) = ApiRepr::from_request(req, &()).await else {
+            panic!("expected rejection")
+        };
+        assert!(
+            rejection
+                .to_string()
+                .starts_with("Failed to negotiate format (accept): api error\n")
+        );
+        Ok(())
+    }
+
+    #[derive(Debug, PreferExtractor)]
+    enum Return {
+        #[prefer(preference = "return=minimal")]
+        Minimal,
+        #[prefer(preference = "return=representation")]
+        Representation,
+    }
+
+    #[tokio::test]
+    async fn test_prefer_extractor() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("prefer", "respond-async, return=representation")
+            .body(Body::from(""))?;
+        let state = ();
+        let prefer = Option::<Return>::from_request(req, &state).await;
+        let Ok(Some(Return::Representation)) = prefer else {
+            panic!("expected return=representation, got {prefer:?}")
+        };
+
+        let req = Request::builder()
+            .header("prefer", "respond-async")
+            .body(Body::from(""))?;
+        let prefer = Option::<Return>::from_request(req, &state).await;
+        let Ok(None) = prefer else {
+            panic!("expected no preference, got {prefer:?}")
+        };
+
+        let response = (Return::Minimal.preference_applied(), "").into_response();
+        assert_eq!("return=minimal", response.headers()[PREFERENCE_APPLIED]);
+        Ok(())
+    }
+
+    #[derive(Debug, PreferExtractor, Default)]
+    enum Handling {
+        #[default]
+        #[prefer(preference = "handling=strict")]
+        Strict,
+        #[prefer(preference = "handling=lenient")]
+        Lenient,
+    }
+
+    #[tokio::test]
+    async fn test_prefer_extractor_default() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder().body(Body::from(""))?;
+        let state = ();
+        let prefer = Handling::from_request(req, &state).await;
+        let Ok(Handling::Strict) = prefer else {
+            panic!("expected handling=strict (default), got {prefer:?}")
+        };
+
+        let req = Request::builder()
+            .header("prefer", "handling=lenient")
+            .body(Body::from(""))?;
+        let prefer = Handling::from_request(req, &state).await;
+        let Ok(Handling::Lenient) = prefer else {
+            panic!("expected handling=lenient, got {prefer:?}")
+        };
+        Ok(())
+    }
 }
@@ -0,0 +1,509 @@
+//! A response that serializes its value in the negotiated format.
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use axum_accept_shared::{AcceptRejection, Dimension, parse_mediatypes};
+use mediatype::MediaType;
+
+use crate::{
+    format::{Format, FormatError},
+    html::{RenderFn, RenderHtml},
+    vary::record_vary,
+};
+
+/// A negotiated representation that can be used in [`Negotiated`].
+///
+/// This is implemented by [`Format`] and derived `AcceptExtractor`s.
+pub trait Representation {
+    /// The media type of the representation, for the `Content-Type` header.
+    fn content_type(&self) -> HeaderValue;
+
+    /// The format to serialize the representation with, if there is one. By
+    /// default, this is the [`Format`] matching [`Representation::content_type`].
+    fn format(&self) -> Option<Format> {
+        let content_type = self.content_type();
+        let mt = MediaType::parse(content_type.to_str().ok()?).ok()?;
+        Format::from_media_type(&mt)
+    }
+}
+
+/// Check whether a representation is `text/html`.
+fn is_html(representation: &impl Representation) -> bool {
+    let content_type = representation.content_type();
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|content_type| MediaType::parse(content_type).ok())
+        .is_some_and(|mt| mt.ty == "text" && mt.subty == "html")
+}
+
+/// Check whether a representation is protobuf.
+#[cfg(feature = "protobuf")]
+fn is_protobuf(representation: &impl Representation) -> bool {
+    let content_type = representation.content_type();
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|content_type| MediaType::parse(content_type).ok())
+        .is_some_and(|mt| crate::protobuf::is_protobuf(&mt))
+}
+
+impl Representation for Format {
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(self.media_type())
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(*self)
+    }
+}
+
+/// Extracts all enabled response [`Format`]s the client accepts, most
+/// preferred first. Form data is only read from requests, so it is left out.
+///
+/// A missing `Accept` header accepts all response formats. If no format is
+/// acceptable, the request is rejected with 406, which lists the media types
+/// of the response formats.
+#[derive(Debug, Clone)]
+pub struct AcceptFormats(pub Vec<Format>);
+
+impl<S> FromRequestParts<S> for AcceptFormats
+where
+    S: Send + Sync,
+{
+    type Rejection = AcceptRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        record_vary(&parts.extensions, Self::HEADER);
+        let mediatypes = parse_mediatypes(&parts.headers)?;
+        let formats = if mediatypes.is_empty() {
+            Format::RESPONSE_FORMATS.to_vec()
+        } else {
+            Format::rank(&mediatypes)
+        };
+        if formats.is_empty() {
+            return Err(AcceptRejection::not_acceptable(
+                &parts.headers,
+                Format::RESPONSE_MEDIA_TYPES,
+            ));
+        }
+        Ok(Self(formats))
+    }
+}
+
+impl Dimension for AcceptFormats {
+    const HEADER: HeaderName = header::ACCEPT;
+}
+
+impl IntoIterator for AcceptFormats {
+    type Item = Format;
+    type IntoIter = std::vec::IntoIter<Format>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Serializes the value in the first representation that can represent it,
+/// and sets the `Content-Type` accordingly. With [`Negotiated::html`], a
+/// `text/html` representation renders the value as HTML instead, and with
+/// [`Negotiated::hypermedia`], HAL, JSON:API and Siren representations render
+/// the value with its links. With `Negotiated::protobuf`, protobuf
+/// representations encode the value as a `prost::Message`. With the `xml`
+/// feature, the value has to implement `ToXml` for the root element and
+/// namespaces of its XML representation.
+///
+/// If none can, it responds with 500 and logs the error with `tracing`, to
+/// not expose details of the value to the client.
+///
+/// ```rust
+/// use axum_accept::{AcceptFormats, Negotiated};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// async fn get_user(formats: AcceptFormats) -> Negotiated<User> {
+///     Negotiated::with_fallbacks(formats, User { name: "Ferris".to_string() })
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Negotiated<T, R = Format> {
+    representations: Vec<R>,
+    value: T,
+    html: Option<RenderFn<T>>,
+    #[cfg(feature = "json")]
+    hypermedia: Option<crate::hypermedia::HypermediaFn<T>>,
+    #[cfg(feature = "protobuf")]
+    protobuf: Option<fn(&T) -> Vec<u8>>,
+}
+
+impl<T, R> Negotiated<T, R> {
+    /// Serialize the value in the given representation.
+    pub fn new(representation: R, value: T) -> Self {
+        Self {
+            representations: vec![representation],
+            value,
+            html: None,
+            #[cfg(feature = "json")]
+            hypermedia: None,
+            #[cfg(feature = "protobuf")]
+            protobuf: None,
+        }
+    }
+
+    /// Serialize the value in the first of the representations that
+    /// succeeds.
+    pub fn with_fallbacks(representations: impl IntoIterator<Item = R>, value: T) -> Self {
+        Self {
+            representations: representations.into_iter().collect(),
+            value,
+            html: None,
+            #[cfg(feature = "json")]
+            hypermedia: None,
+            #[cfg(feature = "protobuf")]
+            protobuf: None,
+        }
+    }
+
+    /// Render the value with [`RenderHtml`] for `text/html` representations.
+    #[must_use]
+    pub fn html(mut self) -> Self
+    where
+        T: RenderHtml,
+    {
+        self.html = Some(T::render_html);
+        self
+    }
+
+    /// Render the value with its links for HAL, JSON:API and Siren
+    /// representations.
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn hypermedia(mut self) -> Self
+    where
+        T: crate::hypermedia::Resource + serde::Serialize,
+    {
+        self.hypermedia = Some(crate::hypermedia::render_hypermedia::<T>);
+        self
+    }
+
+    /// Encode the value as a [`prost::Message`] for protobuf representations,
+    /// with the `Content-Type` of the representation.
+    #[cfg(feature = "protobuf")]
+    #[must_use]
+    pub fn protobuf(mut self) -> Self
+    where
+        T: prost::Message,
+    {
+        self.protobuf = Some(T::encode_to_vec);
+        self
+    }
+}
+
+impl<T, R> Negotiated<T, R>
+where
+    R: Representation,
+{
+    /// Respond with the first representation that can represent the value,
+    /// serializing formats with `serialize`.
+    fn respond(self, serialize: impl Fn(Format, &T) -> Result<Vec<u8>, FormatError>) -> Response {
+        let mut last_error = None;
+        for representation in &self.representations {
+            if let Some(render) = self.html
+                && is_html(representation)
+            {
+                match render(&self.value) {
+                    Ok(html) => {
+                        return (
+                            [(header::CONTENT_TYPE, representation.content_type())],
+                            html,
+                        )
+                            .into_response();
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+                continue;
+            }
+            #[cfg(feature = "json")]
+            if let Some(render) = self.hypermedia
+                && let Some(hypermedia) =
+                    crate::hypermedia::Hypermedia::from_representation(representation)
+            {
+                match render(&self.value, hypermedia) {
+                    Ok(bytes) => {
+                        return (
+                            [(header::CONTENT_TYPE, representation.content_type())],
+                            bytes,
+                        )
+                            .into_response();
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+                continue;
+            }
+            #[cfg(feature = "protobuf")]
+            if let Some(encode) = self.protobuf
+                && is_protobuf(representation)
+            {
+                return (
+                    [(header::CONTENT_TYPE, representation.content_type())],
+                    encode(&self.value),
+                )
+                    .into_response();
+            }
+            let Some(format) = representation.format() else {
+                continue;
+            };
+            match serialize(format, &self.value) {
+                Ok(bytes) => {
+                    return (
+                        [(header::CONTENT_TYPE, representation.content_type())],
+                        bytes,
+                    )
+                        .into_response();
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        if let Some(e) = last_error {
+            tracing::error!("Failed to serialize the response: {e}");
+        } else {
+            tracing::error!("No serializable representation was negotiated");
+        }
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to serialize the response",
+        )
+            .into_response()
+    }
+}
+
+#[cfg(not(feature = "xml"))]
+impl<T, R> IntoResponse for Negotiated<T, R>
+where
+    T: serde::Serialize,
+    R: Representation,
+{
+    fn into_response(self) -> Response {
+        self.respond(Format::serialize)
+    }
+}
+
+/// With the `xml` feature, XML is serialized with the root element and
+/// namespaces of the value's type.
+#[cfg(feature = "xml")]
+impl<T, R> IntoResponse for Negotiated<T, R>
+where
+    T: crate::format::ToXml,
+    R: Representation,
+{
+    fn into_response(self) -> Response {
+        self.respond(|format, value| {
+            if format == Format::Xml {
+                crate::format::to_xml(value)
+            } else {
+                format.serialize(value)
+            }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::{AcceptFormats, Negotiated};
+    use crate::{AcceptExtractor, AcceptRejection, Format, FormatError, RenderHtml};
+    use axum::{
+        body::{Body, to_bytes},
+        extract::{FromRequest, Request},
+        http::{StatusCode, header},
+        response::IntoResponse,
+    };
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct User {
+        name: String,
+    }
+
+    #[cfg(feature = "xml")]
+    impl crate::ToXml for User {
+        const ROOT: Option<&'static str> = Some("user");
+        const NAMESPACES: &'static [(&'static str, &'static str)] = &[("", "urn:example")];
+    }
+
+    impl RenderHtml for User {
+        fn render_html(&self) -> Result<String, FormatError> {
+            if self.name.is_empty() {
+                return Err("Missing name".into());
+            }
+            Ok(format!("<h1>{}</h1>", self.name))
+        }
+    }
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "application/vnd.example.user+json")]
+        User,
+        #[accept(mediatype = "text/html")]
+        TextHtml,
+    }
+
+    #[tokio::test]
+    async fn test_accept_formats() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/html, application/json;q=0.9")
+            .body(Body::from(""))?;
+        let AcceptFormats(formats) = AcceptFormats::from_request(req, &()).await?;
+        assert_eq!(Some(&Format::Json), formats.first());
+
+        let req = Request::builder()
+            .header("accept", "text/html")
+            .body(Body::from(""))?;
+        let result = AcceptFormats::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "application/json;q=0")
+            .body(Body::from(""))?;
+        let result = AcceptFormats::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_format() -> Result<(), Box<dyn std::error::Error>> {
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        let response = Negotiated::new(Format::Json, user).into_response();
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(r#"{"name":"Ferris"}"#, body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_derived() -> Result<(), Box<dyn std::error::Error>> {
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        let response =
+            Negotiated::with_fallbacks([Accept::TextHtml, Accept::User], user).into_response();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "application/vnd.example.user+json",
+            response.headers()[header::CONTENT_TYPE]
+        );
+
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        let response = Negotiated::new(Accept::TextHtml, user).into_response();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+
+        // the serializer error is not exposed
+        let map = std::collections::HashMap::from([((1, 2), 3)]);
+        let response = Negotiated::new(Accept::User, map).into_response();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("Failed to serialize the response", body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_html() -> Result<(), Box<dyn std::error::Error>> {
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        let response = Negotiated::with_fallbacks([Accept::TextHtml, Accept::User], user)
+            .html()
+            .into_response();
+        assert_eq!("text/html", response.headers()[header::CONTENT_TYPE]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("<h1>Ferris</h1>", body);
+
+        // falls back to the next representation if rendering fails
+        let user = User {
+            name: String::new(),
+        };
+        let response = Negotiated::with_fallbacks([Accept::TextHtml, Accept::User], user)
+            .html()
+            .into_response();
+        assert_eq!(
+            "application/vnd.example.user+json",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "askama")]
+    #[tokio::test]
+    async fn test_negotiated_askama() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(askama::Template, Serialize)]
+        #[template(source = "<p>{{ greeting }}</p>", ext = "html")]
+        struct Greeting {
+            greeting: String,
+        }
+
+        #[cfg(feature = "xml")]
+        impl crate::ToXml for Greeting {}
+
+        let greeting = Greeting {
+            greeting: "<hello>".to_string(),
+        };
+        let response = Negotiated::new(Accept::TextHtml, greeting)
+            .html()
+            .into_response();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("<p>&#60;hello&#62;</p>", body);
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[tokio::test]
+    async fn test_negotiated_fallback() -> Result<(), Box<dyn std::error::Error>> {
+        // TOML can't represent a top-level list
+        let response =
+            Negotiated::with_fallbacks([Format::Toml, Format::Json], vec![1, 2, 3]).into_response();
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("[1,2,3]", body);
+        Ok(())
+    }
+
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn test_negotiated_xml() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(AcceptExtractor)]
+        enum AcceptXml {
+            #[accept(mediatype = "application/vnd.example.user+xml")]
+            User,
+        }
+
+        let user = User {
+            name: "Ferris".to_string(),
+        };
+        // the root element and namespaces of the type are used
+        let response = Negotiated::with_fallbacks([AcceptXml::User], user).into_response();
+        assert_eq!(
+            "application/vnd.example.user+xml",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(
+            r#"<user xmlns="urn:example"><name>Ferris</name></user>"#,
+            body
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+//! A `PATCH` extractor supporting JSON Merge Patch (RFC 7396) and JSON Patch
+//! (RFC 6902), requires the `patch` feature.
+use std::{fmt::Display, marker::PhantomData};
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{ContentTypeExtractor, NegotiatedBodyRejection, format::Format};
+
+/// The patch document of a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchDocument {
+    /// An `application/merge-patch+json` document (RFC 7396).
+    Merge(Value),
+    /// An `application/json-patch+json` document (RFC 6902).
+    Json(json_patch::Patch),
+}
+
+/// The `Content-Type` of a patch document.
+#[derive(ContentTypeExtractor)]
+enum PatchContentType {
+    #[content_type(mediatype = "application/merge-patch+json")]
+    Merge,
+    #[content_type(mediatype = "application/json-patch+json")]
+    Json,
+}
+
+/// Extracts a JSON Merge Patch or JSON Patch document, depending on the
+/// `Content-Type` of the request, which can be applied to a `T`.
+///
+/// Other content types are rejected with 415, listing both in the
+/// `Accept-Patch` header of `PATCH` requests.
+///
+/// ```rust
+/// use axum_accept::Patch;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// async fn patch_user(patch: Patch<User>) -> Result<String, axum_accept::PatchError> {
+///     let mut user = User { name: "Ferris".to_string() };
+///     patch.apply(&mut user)?;
+///     Ok(user.name)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Patch<T = Value> {
+    document: PatchDocument,
+    _target: PhantomData<fn() -> T>,
+}
+
+impl<T> Patch<T> {
+    /// The media types a patch can be sent as, in the order they are
+    /// advertised.
+    pub const MEDIA_TYPES: &[&str] = &[
+        "application/merge-patch+json",
+        "application/json-patch+json",
+    ];
+
+    /// Get the patch document.
+    #[must_use]
+    pub fn document(&self) -> &PatchDocument {
+        &self.document
+    }
+
+    /// Turn this into the patch document.
+    #[must_use]
+    pub fn into_document(self) -> PatchDocument {
+        self.document
+    }
+
+    /// Apply the patch to a JSON value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a JSON Patch operation fails. The value is left
+    /// untouched in that case.
+    pub fn apply_to_value(&self, value: &mut Value) -> Result<(), PatchError> {
+        match &self.document {
+            PatchDocument::Merge(patch) => json_patch::merge(value, patch),
+            PatchDocument::Json(patch) => {
+                json_patch::patch(value, patch).map_err(PatchError::Patch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Patch<T> {
+    /// Apply the patch to a document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document can't be represented as JSON, a JSON
+    /// Patch operation fails or the patched document is no valid `T`. The
+    /// document is left untouched in that case.
+    pub fn apply(&self, target: &mut T) -> Result<(), PatchError> {
+        let mut value = serde_json::to_value(&*target).map_err(PatchError::Serialize)?;
+        self.apply_to_value(&mut value)?;
+        *target = serde_json::from_value(value).map_err(PatchError::Deserialize)?;
+        Ok(())
+    }
+}
+
+impl<T, S> FromRequest<S> for Patch<T>
+where
+    S: Send + Sync,
+{
+    type Rejection = NegotiatedBodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+        let content_type = PatchContentType::from_request_parts(&mut parts, state).await?;
+        let req = Request::from_parts(parts, body);
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(NegotiatedBodyRejection::Body)?;
+
+        let document = match content_type {
+            PatchContentType::Merge => serde_json::from_slice(&bytes).map(PatchDocument::Merge),
+            PatchContentType::Json => serde_json::from_slice(&bytes).map(PatchDocument::Json),
+        }
+        .map_err(|e| NegotiatedBodyRejection::InvalidBody(Format::Json, e.into()))?;
+
+        Ok(Self {
+            document,
+            _target: PhantomData,
+        })
+    }
+}
+
+/// The error returned if a [`Patch`] could not be applied.
+#[derive(Debug)]
+pub enum PatchError {
+    /// The document could not be serialized to JSON.
+    Serialize(serde_json::Error),
+    /// A JSON Patch operation failed.
+    Patch(json_patch::PatchError),
+    /// The patched document could not be deserialized.
+    Deserialize(serde_json::Error),
+}
+
+impl PatchError {
+    /// Get the status and message for an error.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::Serialize(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize the document: {e}"),
+            ),
+            Self::Patch(e) => (
+                StatusCode::CONFLICT,
+                format!("Failed to apply the patch: {e}"),
+            ),
+            Self::Deserialize(e) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("The patched document is invalid: {e}"),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for PatchError {
+    fn into_response(self) -> Response {
+        self.status_and_message().into_response()
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Patch, PatchError};
+    use crate::{ACCEPT_PATCH, NegotiatedBodyRejection};
+    use axum::{
+        body::Body,
+        extract::{FromRequest, Request},
+        http::{Method, StatusCode},
+        response::IntoResponse,
+    };
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Value, json};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+
+    fn patch_request(content_type: &str, body: &str) -> Result<Request, axum::http::Error> {
+        Request::builder()
+            .method(Method::PATCH)
+            .header("content-type", content_type)
+            .body(Body::from(body.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_patch_merge() -> Result<(), Box<dyn std::error::Error>> {
+        let req = patch_request("application/merge-patch+json", r#"{"age":10}"#)?;
+        let patch = Patch::<User>::from_request(req, &()).await?;
+        let mut user = User {
+            name: "Ferris".to_string(),
+            age: 9,
+        };
+        patch.apply(&mut user)?;
+        assert_eq!(10, user.age);
+
+        let req = patch_request(
+            "Application/Merge-Patch+JSON; charset=utf-8",
+            r#"{"age":11}"#,
+        )?;
+        Patch::<User>::from_request(req, &())
+            .await?
+            .apply(&mut user)?;
+        assert_eq!(11, user.age);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_json() -> Result<(), Box<dyn std::error::Error>> {
+        let req = patch_request(
+            "application/json-patch+json",
+            r#"[{"op":"test","path":"/name","value":"Ferris"},{"op":"remove","path":"/age"}]"#,
+        )?;
+        let patch = Patch::<Value>::from_request(req, &()).await?;
+        let mut value = json!({ "name": "Ferris", "age": 9 });
+        patch.apply(&mut value)?;
+        assert_eq!(json!({ "name": "Ferris" }), value);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let mut user = User {
+            name: "Ferris".to_string(),
+            age: 9,
+        };
+
+        let req = patch_request(
+            "application/json-patch+json",
+            r#"[{"op":"test","path":"/name","value":"Corro"}]"#,
+        )?;
+        let patch = Patch::<User>::from_request(req, &()).await?;
+        let Err(e @ PatchError::Patch(_)) = patch.apply(&mut user) else {
+            panic!("expected failed test operation")
+        };
+        assert_eq!(StatusCode::CONFLICT, e.into_response().status());
+
+        let req = patch_request("application/merge-patch+json", r#"{"age":null}"#)?;
+        let patch = Patch::<User>::from_request(req, &()).await?;
+        let Err(e @ PatchError::Deserialize(_)) = patch.apply(&mut user) else {
+            panic!("expected invalid patched document")
+        };
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, e.into_response().status());
+        assert_eq!(9, user.age);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_patch_unsupported() -> Result<(), Box<dyn std::error::Error>> {
+        let req = patch_request("application/json", r#"{"age":10}"#)?;
+        let result = Patch::<User>::from_request(req, &()).await;
+        let Err(rejection @ NegotiatedBodyRejection::ContentType(_)) = result else {
+            panic!("expected unsupported media type, got {result:?}")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert_eq!(
+            "application/merge-patch+json, application/json-patch+json",
+            response.headers()[ACCEPT_PATCH]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! A typed extractor for the `Prefer` header (RFC 7240) and the matching
+//! `Preference-Applied` response header.
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderValue, request::Parts},
+    response::{IntoResponseParts, ResponseParts},
+};
+use axum_accept_shared::{
+    Dimension, PREFER, PREFERENCE_APPLIED, PreferRejection, Preference, parse_preferences,
+};
+
+use crate::vary::record_vary;
+
+/// All preferences of the `Prefer` headers of a request, in order.
+///
+/// ```rust
+/// use axum::{http::StatusCode, response::IntoResponse};
+/// use axum_accept::{Prefer, PreferenceApplied};
+///
+/// async fn create(prefer: Prefer) -> impl IntoResponse {
+///     if prefer.return_minimal() {
+///         let applied = PreferenceApplied::from(prefer.get("return").cloned());
+///         return (StatusCode::NO_CONTENT, applied, "").into_response();
+///     }
+///     (StatusCode::CREATED, "the full representation").into_response()
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Prefer(pub Vec<Preference>);
+
+impl Prefer {
+    /// Get the first preference with the given name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Preference> {
+        self.0
+            .iter()
+            .find(|preference| preference.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Check whether the client prefers `return=minimal`.
+    #[must_use]
+    pub fn return_minimal(&self) -> bool {
+        self.get("return")
+            .is_some_and(|preference| preference.matches("return", Some("minimal")))
+    }
+
+    /// Check whether the client prefers `return=representation`.
+    #[must_use]
+    pub fn return_representation(&self) -> bool {
+        self.get("return")
+            .is_some_and(|preference| preference.matches("return", Some("representation")))
+    }
+
+    /// Check whether the client prefers `respond-async`.
+    #[must_use]
+    pub fn respond_async(&self) -> bool {
+        self.get("respond-async").is_some()
+    }
+
+    /// Get the time the client is willing to wait, from `wait`.
+    #[must_use]
+    pub fn wait(&self) -> Option<Duration> {
+        self.get("wait")?
+            .value
+            .as_deref()?
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+
+    /// Check whether the client prefers `handling=lenient`.
+    #[must_use]
+    pub fn handling_lenient(&self) -> bool {
+        self.get("handling")
+            .is_some_and(|preference| preference.matches("handling", Some("lenient")))
+    }
+
+    /// Check whether the client prefers `handling=strict`.
+    #[must_use]
+    pub fn handling_strict(&self) -> bool {
+        self.get("handling")
+            .is_some_and(|preference| preference.matches("handling", Some("strict")))
+    }
+}
+
+impl<S> FromRequestParts<S> for Prefer
+where
+    S: Send + Sync,
+{
+    type Rejection = PreferRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        record_vary(&parts.extensions, PREFER);
+        parse_preferences(&parts.headers).map(Self)
+    }
+}
+
+impl Dimension for Prefer {
+    const HEADER: axum::http::HeaderName = PREFER;
+}
+
+/// Sets the `Preference-Applied` header to the preferences that were honored.
+///
+/// If there are no preferences, the header is not set.
+#[derive(Debug, Clone, Default)]
+pub struct PreferenceApplied(pub Vec<Preference>);
+
+impl From<Preference> for PreferenceApplied {
+    fn from(preference: Preference) -> Self {
+        Self(vec![preference])
+    }
+}
+
+impl From<Option<Preference>> for PreferenceApplied {
+    fn from(preference: Option<Preference>) -> Self {
+        Self(preference.into_iter().collect())
+    }
+}
+
+impl FromIterator<Preference> for PreferenceApplied {
+    fn from_iter<I: IntoIterator<Item = Preference>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoResponseParts for PreferenceApplied {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let value = self
+            .0
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Ok(value) = HeaderValue::from_str(&value)
+            && !value.is_empty()
+        {
+            res.headers_mut().insert(PREFERENCE_APPLIED, value);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Prefer, PreferenceApplied};
+    use crate::{PREFERENCE_APPLIED, Preference};
+    use axum::{
+        body::Body,
+        extract::{FromRequest, Request},
+        response::IntoResponse,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_prefer() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("prefer", "return=minimal, wait=10, handling=lenient")
+            .body(Body::from(""))?;
+        let prefer = Prefer::from_request(req, &()).await?;
+        assert!(prefer.return_minimal());
+        assert!(!prefer.return_representation());
+        assert!(!prefer.respond_async());
+        assert_eq!(Some(Duration::from_secs(10)), prefer.wait());
+        assert!(prefer.handling_lenient());
+        Ok(())
+    }
+
+    #[test]
+    fn test_preference_applied() {
+        let response = (
+            PreferenceApplied::from_iter([
+                Preference::new("return", Some("minimal")),
+                Preference::new("respond-async", None),
+            ]),
+            "",
+        )
+            .into_response();
+        assert_eq!(
+            "return=minimal, respond-async",
+            response.headers()[PREFERENCE_APPLIED]
+        );
+
+        let response = (PreferenceApplied::default(), "").into_response();
+        assert!(!response.headers().contains_key(PREFERENCE_APPLIED));
+    }
+}
@@ -0,0 +1,273 @@
+//! Protocol Buffers request bodies and responses for `prost` messages.
+use std::fmt::Display;
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request, rejection::BytesRejection},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use axum_accept_shared::{ContentTypeRejection, parse_content_type, supported_media_types_header};
+use mediatype::MediaType;
+use prost::Message;
+
+/// Encodes and decodes [`prost::Message`]s as `application/x-protobuf` or
+/// `application/protobuf`.
+///
+/// As an extractor, it decodes the request body if the `Content-Type` is one
+/// of those and rejects other media types with 415. As a response, it encodes
+/// the message with `Content-Type: application/x-protobuf`.
+///
+/// To serve JSON to browsers and protobuf to other services from the same
+/// route, negotiate both and respond with [`Negotiated::protobuf`], which
+/// echoes the negotiated media type:
+///
+/// ```rust
+/// use axum_accept::{AcceptExtractor, Negotiated};
+/// use serde::Serialize;
+///
+/// #[derive(AcceptExtractor)]
+/// enum Accept {
+///     #[accept(mediatype = "application/json")]
+///     Json,
+///     #[accept(mediatype = "application/protobuf")]
+///     Protobuf,
+///     #[accept(mediatype = "application/x-protobuf")]
+///     XProtobuf,
+/// }
+///
+/// #[derive(Clone, PartialEq, prost::Message, Serialize)]
+/// struct User {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// async fn get_user(accept: Accept) -> Negotiated<User, Accept> {
+///     let user = User { name: "Ferris".to_string() };
+///     Negotiated::new(accept, user).protobuf()
+/// }
+/// ```
+///
+/// [`Negotiated::protobuf`]: crate::Negotiated::protobuf
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Protobuf<T>(pub T);
+
+/// The media types of protobuf bodies, the first one is used for responses.
+pub(crate) const MEDIA_TYPES: &[&str] = &["application/x-protobuf", "application/protobuf"];
+
+/// Check whether a media type names a protobuf body.
+pub(crate) fn is_protobuf(mt: &MediaType) -> bool {
+    mt.ty == "application"
+        && mt.suffix.is_none()
+        && (mt.subty == "x-protobuf" || mt.subty == "protobuf")
+}
+
+impl<T, S> FromRequest<S> for Protobuf<T>
+where
+    T: Message + Default,
+    S: Send + Sync,
+{
+    type Rejection = ProtobufRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_protobuf = parse_content_type(req.headers())?.is_some_and(|mt| is_protobuf(&mt));
+        if !is_protobuf {
+            return Err(ContentTypeRejection::UnsupportedMediaType(
+                supported_media_types_header(req.method()),
+                MEDIA_TYPES,
+            )
+            .into());
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(ProtobufRejection::Body)?;
+
+        T::decode(bytes)
+            .map(Self)
+            .map_err(ProtobufRejection::InvalidBody)
+    }
+}
+
+impl<T> IntoResponse for Protobuf<T>
+where
+    T: Message,
+{
+    fn into_response(self) -> Response {
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(MEDIA_TYPES[0]),
+            )],
+            self.0.encode_to_vec(),
+        )
+            .into_response()
+    }
+}
+
+/// The error type returned in the `FromRequest` implementation of
+/// [`Protobuf`].
+#[derive(Debug)]
+pub enum ProtobufRejection {
+    /// The content type was invalid or not protobuf.
+    ContentType(ContentTypeRejection),
+    /// The body could not be read, e.g. because it exceeded the body limit.
+    Body(BytesRejection),
+    /// The body could not be decoded.
+    InvalidBody(prost::DecodeError),
+}
+
+impl ProtobufRejection {
+    /// Get the status and message for an error.
+    #[must_use]
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
+            Self::ContentType(e) => e.status_and_message(),
+            Self::Body(e) => (e.status(), e.body_text()),
+            Self::InvalidBody(e) => (
+                StatusCode::BAD_REQUEST,
+                format!("Failed to decode the protobuf body: {e}"),
+            ),
+        }
+    }
+}
+
+impl From<ContentTypeRejection> for ProtobufRejection {
+    fn from(e: ContentTypeRejection) -> Self {
+        Self::ContentType(e)
+    }
+}
+
+impl IntoResponse for ProtobufRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::ContentType(e) => e.into_response(),
+            Self::Body(e) => e.into_response(),
+            Self::InvalidBody(_) => self.status_and_message().into_response(),
+        }
+    }
+}
+
+impl Display for ProtobufRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, message) = self.status_and_message();
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for ProtobufRejection {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Protobuf, ProtobufRejection};
+    use crate::{ACCEPT_POST, AcceptExtractor, ContentTypeRejection, Negotiated};
+    use axum::{
+        body::{Body, to_bytes},
+        extract::{FromRequest, FromRequestParts, Request},
+        http::{Method, StatusCode, header},
+        response::IntoResponse,
+    };
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message, serde::Serialize)]
+    struct User {
+        #[prost(string, tag = "1")]
+        name: String,
+    }
+
+    #[cfg(feature = "xml")]
+    impl crate::ToXml for User {}
+
+    fn user() -> User {
+        User {
+            name: "Ferris".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_body() -> Result<(), Box<dyn std::error::Error>> {
+        for content_type in ["application/x-protobuf", "Application/Protobuf"] {
+            let req = Request::builder()
+                .header("content-type", content_type)
+                .body(Body::from(user().encode_to_vec()))?;
+            let Protobuf(decoded) = Protobuf::<User>::from_request(req, &()).await?;
+            assert_eq!(user(), decoded);
+        }
+
+        let req = Request::builder()
+            .header("content-type", "application/x-protobuf")
+            .body(Body::from(vec![0xff]))?;
+        let result = Protobuf::<User>::from_request(req, &()).await;
+        let Err(ProtobufRejection::InvalidBody(_)) = result else {
+            panic!("expected invalid body, got {result:?}")
+        };
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_unsupported_media_type() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .method(Method::POST)
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))?;
+        let result = Protobuf::<User>::from_request(req, &()).await;
+        let Err(
+            rejection @ ProtobufRejection::ContentType(ContentTypeRejection::UnsupportedMediaType(
+                ..,
+            )),
+        ) = result
+        else {
+            panic!("expected unsupported media type, got {result:?}")
+        };
+        let response = rejection.into_response();
+        assert_eq!(StatusCode::UNSUPPORTED_MEDIA_TYPE, response.status());
+        assert_eq!(
+            "application/x-protobuf, application/protobuf",
+            response.headers()[ACCEPT_POST]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protobuf_response() -> Result<(), Box<dyn std::error::Error>> {
+        let response = Protobuf(user()).into_response();
+        assert_eq!(
+            "application/x-protobuf",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(user(), User::decode(body)?);
+        Ok(())
+    }
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "application/protobuf")]
+        Protobuf,
+        #[accept(mediatype = "application/x-protobuf")]
+        XProtobuf,
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_protobuf() -> Result<(), Box<dyn std::error::Error>> {
+        for (accept, content_type) in [
+            ("application/protobuf", "application/protobuf"),
+            ("application/x-protobuf", "application/x-protobuf"),
+        ] {
+            let (mut parts, ()) = Request::builder()
+                .header("accept", accept)
+                .body(())?
+                .into_parts();
+            let accept = Accept::from_request_parts(&mut parts, &()).await?;
+            let response = Negotiated::new(accept, user()).protobuf().into_response();
+            assert_eq!(content_type, response.headers()[header::CONTENT_TYPE]);
+            let body = to_bytes(response.into_body(), usize::MAX).await?;
+            assert_eq!(user(), User::decode(body)?);
+        }
+
+        // without `protobuf()`, protobuf representations can't be served
+        let response = Negotiated::new(Accept::Protobuf, user()).into_response();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        Ok(())
+    }
+}
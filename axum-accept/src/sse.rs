@@ -0,0 +1,131 @@
+//! A response that is either a live event stream or a snapshot, depending on
+//! the negotiated representation.
+use axum::response::{IntoResponse, Response, sse::Sse};
+use mediatype::MediaType;
+
+use crate::negotiated::{Negotiated, Representation};
+
+/// Check whether a representation is `text/event-stream`.
+pub fn is_event_stream(representation: &impl Representation) -> bool {
+    let content_type = representation.content_type();
+    content_type
+        .to_str()
+        .ok()
+        .and_then(|content_type| MediaType::parse(content_type).ok())
+        .is_some_and(|mt| mt.ty == "text" && mt.subty == "event-stream")
+}
+
+/// Responds with the server-sent events if the negotiated representation is
+/// `text/event-stream`, and with the serialized snapshot otherwise.
+///
+/// The snapshot function is only called if there's no event stream, and its
+/// value is serialized like [`Negotiated`] does.
+///
+/// ```rust
+/// use std::convert::Infallible;
+///
+/// use axum::response::sse::{Event, Sse};
+/// use axum_accept::{AcceptExtractor, LiveOrSnapshot};
+/// use futures_util::stream::{self, Stream};
+/// use serde::Serialize;
+///
+/// #[derive(AcceptExtractor)]
+/// enum Accept {
+///     #[accept(mediatype = "application/json")]
+///     Json,
+///     #[accept(mediatype = "text/event-stream")]
+///     EventStream,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Status {
+///     healthy: bool,
+/// }
+///
+/// async fn status(
+///     accept: Accept,
+/// ) -> LiveOrSnapshot<Accept, impl Stream<Item = Result<Event, Infallible>>, impl FnOnce() -> Status> {
+///     let events = stream::iter([Ok(Event::default().data("healthy"))]);
+///     LiveOrSnapshot::new(accept, Sse::new(events), || Status { healthy: true })
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LiveOrSnapshot<R, S, F> {
+    representation: R,
+    live: Sse<S>,
+    snapshot: F,
+}
+
+impl<R, S, F> LiveOrSnapshot<R, S, F> {
+    /// Respond with `live` or the value of `snapshot`, depending on
+    /// `representation`.
+    pub fn new(representation: R, live: Sse<S>, snapshot: F) -> Self {
+        Self {
+            representation,
+            live,
+            snapshot,
+        }
+    }
+}
+
+impl<R, S, F, T> IntoResponse for LiveOrSnapshot<R, S, F>
+where
+    R: Representation,
+    Sse<S>: IntoResponse,
+    F: FnOnce() -> T,
+    Negotiated<T, R>: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        if is_event_stream(&self.representation) {
+            self.live.into_response()
+        } else {
+            Negotiated::new(self.representation, (self.snapshot)()).into_response()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::LiveOrSnapshot;
+    use crate::AcceptExtractor;
+    use axum::{
+        body::to_bytes,
+        http::header,
+        response::{
+            IntoResponse,
+            sse::{Event, Sse},
+        },
+    };
+    use futures_util::stream;
+    use std::convert::Infallible;
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "application/json")]
+        Json,
+        #[accept(mediatype = "text/event-stream")]
+        EventStream,
+    }
+
+    fn respond(accept: Accept) -> axum::response::Response {
+        let events = stream::iter([Ok::<_, Infallible>(Event::default().data("1"))]);
+        LiveOrSnapshot::new(accept, Sse::new(events), || vec![1]).into_response()
+    }
+
+    #[tokio::test]
+    async fn test_live_or_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+        let response = respond(Accept::EventStream);
+        assert_eq!(
+            "text/event-stream",
+            response.headers()[header::CONTENT_TYPE]
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("data: 1\n\n", body);
+
+        let response = respond(Accept::Json);
+        assert_eq!("application/json", response.headers()[header::CONTENT_TYPE]);
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!("[1]", body);
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+//! Streams of serializable values as a JSON array or line-delimited JSON.
+use axum::{
+    body::{Body, Bytes},
+    extract::FromRequestParts,
+    http::{HeaderName, HeaderValue, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use axum_accept_shared::{AcceptRejection, Dimension, parse_mediatypes, quality};
+use futures_util::{Stream, StreamExt, stream};
+use mediatype::{MediaType, names::_STAR};
+use serde::Serialize;
+
+use crate::{format::Format, negotiated::Representation, vary::record_vary};
+
+/// How a [`JsonStream`] is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonStreamFormat {
+    /// `application/json`, a single array, buffered until the stream ends.
+    Array,
+    /// `application/x-ndjson`, one value per line.
+    NdJson,
+    /// `application/jsonl`, one value per line.
+    JsonLines,
+    /// `application/json-seq` (RFC 7464), each value prefixed with a record
+    /// separator and followed by a line feed.
+    JsonSeq,
+}
+
+impl JsonStreamFormat {
+    /// All stream formats, in the order wildcards prefer them.
+    const ALL: [Self; 4] = [Self::Array, Self::NdJson, Self::JsonLines, Self::JsonSeq];
+
+    /// The media types of all stream formats.
+    pub const MEDIA_TYPES: &[&str] = &[
+        "application/json",
+        "application/x-ndjson",
+        "application/jsonl",
+        "application/json-seq",
+    ];
+
+    /// Get the stream format for a media type. Wildcards match the first
+    /// format, the array.
+    #[must_use]
+    pub fn from_media_type(mt: &MediaType) -> Option<Self> {
+        let is = |ty: &str, subty: &str| mt.ty == ty && mt.subty == subty && mt.suffix.is_none();
+        if mt.ty == _STAR
+            || (mt.ty == "application" && mt.subty == _STAR)
+            || is("application", "json")
+        {
+            Some(Self::Array)
+        } else if is("application", "x-ndjson") {
+            Some(Self::NdJson)
+        } else if is("application", "jsonl") {
+            Some(Self::JsonLines)
+        } else if is("application", "json-seq") {
+            Some(Self::JsonSeq)
+        } else {
+            None
+        }
+    }
+
+    /// Get the stream format of a negotiated representation, e.g. a variant
+    /// of a derived `AcceptExtractor`.
+    pub fn from_representation(representation: &impl Representation) -> Option<Self> {
+        let content_type = representation.content_type();
+        let mt = MediaType::parse(content_type.to_str().ok()?).ok()?;
+        Self::from_media_type(&mt)
+    }
+
+    /// The media type of the stream format.
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Array => "application/json",
+            Self::NdJson => "application/x-ndjson",
+            Self::JsonLines => "application/jsonl",
+            Self::JsonSeq => "application/json-seq",
+        }
+    }
+}
+
+impl Representation for JsonStreamFormat {
+    fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(self.media_type())
+    }
+
+    fn format(&self) -> Option<Format> {
+        match self {
+            Self::Array => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Extracts the most preferred stream format the client accepts, by `q`. A
+/// format listed with `q=0` is never chosen, also not for a wildcard. A
+/// missing `Accept` header gets the array. If no stream format is acceptable,
+/// the request is rejected with 406.
+impl<S> FromRequestParts<S> for JsonStreamFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = AcceptRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        record_vary(&parts.extensions, Self::HEADER);
+        let mediatypes = parse_mediatypes(&parts.headers)?;
+        if mediatypes.is_empty() {
+            return Ok(Self::Array);
+        }
+        // the formats the client explicitly rejected
+        let rejected: Vec<Self> = mediatypes
+            .iter()
+            .filter(|mt| quality(mt) == 0 && mt.subty != _STAR)
+            .filter_map(Self::from_media_type)
+            .collect();
+        // the ranges are sorted by q, so the first match is the most preferred
+        mediatypes
+            .iter()
+            .filter(|mt| quality(mt) > 0)
+            .find_map(|mt| {
+                if mt.subty == _STAR {
+                    Self::from_media_type(mt)?;
+                    Self::ALL.into_iter().find(|f| !rejected.contains(f))
+                } else {
+                    Self::from_media_type(mt).filter(|f| !rejected.contains(f))
+                }
+            })
+            .ok_or_else(|| {
+                AcceptRejection::not_acceptable(&parts.headers, JsonStreamFormat::MEDIA_TYPES)
+            })
+    }
+}
+
+impl Dimension for JsonStreamFormat {
+    const HEADER: HeaderName = header::ACCEPT;
+}
+
+/// Renders a stream of values in a [`JsonStreamFormat`].
+///
+/// The line-delimited formats are streamed as the values arrive, the array is
+/// sent once the stream has ended. If a value fails to serialize, the body is
+/// aborted.
+///
+/// ```rust
+/// use axum_accept::{JsonStream, JsonStreamFormat};
+/// use futures_util::{Stream, StreamExt, stream};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct LogLine {
+///     message: String,
+/// }
+///
+/// async fn logs(format: JsonStreamFormat) -> JsonStream<impl Stream<Item = LogLine>> {
+///     let lines = stream::iter(["started", "stopped"]).map(|message| LogLine {
+///         message: message.to_string(),
+///     });
+///     JsonStream::new(format, lines)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonStream<S> {
+    format: JsonStreamFormat,
+    stream: S,
+}
+
+impl<S> JsonStream<S> {
+    /// Render the stream in the given format.
+    pub fn new(format: JsonStreamFormat, stream: S) -> Self {
+        Self { format, stream }
+    }
+}
+
+impl<S> IntoResponse for JsonStream<S>
+where
+    S: Stream + Send + 'static,
+    S::Item: Serialize + Send,
+{
+    fn into_response(self) -> Response {
+        let content_type = self.format.content_type();
+        let body = match self.format {
+            JsonStreamFormat::Array => Body::from_stream(stream::once(async move {
+                let values: Vec<S::Item> = self.stream.collect().await;
+                serde_json::to_vec(&values).map(Bytes::from)
+            })),
+            format => Body::from_stream(self.stream.map(move |value| {
+                let mut bytes = Vec::new();
+                if format == JsonStreamFormat::JsonSeq {
+                    bytes.push(0x1e);
+                }
+                serde_json::to_writer(&mut bytes, &value)?;
+                bytes.push(b'\n');
+                Ok::<_, serde_json::Error>(Bytes::from(bytes))
+            })),
+        };
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonStream, JsonStreamFormat};
+    use crate::{AcceptExtractor, AcceptRejection};
+    use axum::{
+        body::{Body, to_bytes},
+        extract::{FromRequest, Request},
+        http::header,
+        response::IntoResponse,
+    };
+    use futures_util::stream;
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "application/jsonl")]
+        JsonLines,
+        #[accept(mediatype = "text/html")]
+        TextHtml,
+    }
+
+    #[tokio::test]
+    async fn test_json_stream_format() -> Result<(), Box<dyn std::error::Error>> {
+        let req = Request::builder()
+            .header("accept", "text/html, application/json-seq;q=0.9, */*;q=0.1")
+            .body(Body::from(""))?;
+        let format = JsonStreamFormat::from_request(req, &()).await?;
+        assert_eq!(JsonStreamFormat::JsonSeq, format);
+
+        let req = Request::builder().body(Body::from(""))?;
+        let format = JsonStreamFormat::from_request(req, &()).await?;
+        assert_eq!(JsonStreamFormat::Array, format);
+
+        let req = Request::builder()
+            .header("accept", "application/json;q=0.5, application/x-ndjson")
+            .body(Body::from(""))?;
+        let format = JsonStreamFormat::from_request(req, &()).await?;
+        assert_eq!(JsonStreamFormat::NdJson, format);
+
+        let req = Request::builder()
+            .header("accept", "application/json;q=0, */*")
+            .body(Body::from(""))?;
+        let format = JsonStreamFormat::from_request(req, &()).await?;
+        assert_eq!(JsonStreamFormat::NdJson, format);
+
+        let req = Request::builder()
+            .header("accept", "application/x-ndjson;q=0")
+            .body(Body::from(""))?;
+        let result = JsonStreamFormat::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+
+        let req = Request::builder()
+            .header("accept", "text/html")
+            .body(Body::from(""))?;
+        let result = JsonStreamFormat::from_request(req, &()).await;
+        let Err(AcceptRejection::NoSupportedMediaTypeFound { .. }) = result else {
+            panic!("expected no supported media type found, got {result:?}")
+        };
+
+        assert_eq!(
+            Some(JsonStreamFormat::JsonLines),
+            JsonStreamFormat::from_representation(&Accept::JsonLines)
+        );
+        assert_eq!(
+            None,
+            JsonStreamFormat::from_representation(&Accept::TextHtml)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let cases = [
+            (JsonStreamFormat::Array, "application/json", "[1,2,3]"),
+            (
+                JsonStreamFormat::NdJson,
+                "application/x-ndjson",
+                "1\n2\n3\n",
+            ),
+            (
+                JsonStreamFormat::JsonLines,
+                "application/jsonl",
+                "1\n2\n3\n",
+            ),
+            (
+                JsonStreamFormat::JsonSeq,
+                "application/json-seq",
+                "\u{1e}1\n\u{1e}2\n\u{1e}3\n",
+            ),
+        ];
+        for (format, content_type, expected) in cases {
+            let response = JsonStream::new(format, stream::iter([1, 2, 3])).into_response();
+            assert_eq!(content_type, response.headers()[header::CONTENT_TYPE]);
+            let body = to_bytes(response.into_body(), usize::MAX).await?;
+            assert_eq!(expected, body);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! A layer that adds the headers used for negotiation to the `Vary` header of
+//! the response.
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use axum::http::{Extensions, HeaderName, HeaderValue, Request, Response, header::VARY};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Collects the headers used by extractors while handling a request.
+#[derive(Debug, Clone, Default)]
+struct VaryRecorder(Arc<Mutex<Vec<HeaderName>>>);
+
+/// Record that the response depends on the given request header.
+///
+/// The derived extractors call this themselves, custom extractors can use it
+/// to be picked up by [`VaryLayer`] as well. Without the layer, this does
+/// nothing.
+pub fn record_vary(extensions: &Extensions, header: HeaderName) {
+    if let Some(VaryRecorder(headers)) = extensions.get::<VaryRecorder>()
+        && let Ok(mut headers) = headers.lock()
+        && !headers.contains(&header)
+    {
+        headers.push(header);
+    }
+}
+
+/// A layer that adds the request headers the extractors negotiated on to the
+/// `Vary` header of the response, merged with any existing values.
+///
+/// ```rust
+/// use axum::{Router, routing::get};
+/// use axum_accept::{AcceptExtractor, VaryLayer};
+///
+/// #[derive(AcceptExtractor)]
+/// enum Accept {
+///     #[accept(mediatype="text/plain")]
+///     TextPlain,
+/// }
+///
+/// async fn handler(_accept: Accept) -> &'static str {
+///     "hello world"
+/// }
+///
+/// // every response of handler now has `Vary: accept`
+/// let app: Router = Router::new().route("/", get(handler)).layer(VaryLayer);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VaryLayer;
+
+impl<S> Layer<S> for VaryLayer {
+    type Service = VaryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        VaryService { inner }
+    }
+}
+
+/// The service created by [`VaryLayer`].
+#[derive(Debug, Clone)]
+pub struct VaryService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for VaryService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let recorder = VaryRecorder::default();
+        req.extensions_mut().insert(recorder.clone());
+        let future = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Ok(headers) = recorder.0.lock() {
+                merge_vary(response.headers_mut(), &headers);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Merge the headers into the `Vary` header, skipping the ones already
+/// present. A `Vary: *` is left untouched.
+fn merge_vary(response_headers: &mut axum::http::HeaderMap, headers: &[HeaderName]) {
+    if headers.is_empty() {
+        return;
+    }
+
+    let mut values: Vec<String> = response_headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect();
+    if values.iter().any(|value| value == "*") {
+        return;
+    }
+
+    for header in headers {
+        if !values
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(header.as_str()))
+        {
+            values.push(header.to_string());
+        }
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&values.join(", ")) {
+        response_headers.insert(VARY, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VaryLayer, merge_vary};
+    use crate::{AcceptExtractor, AcceptRejection};
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, Request, StatusCode, header},
+        response::IntoResponse,
+        routing::get,
+    };
+    use tower::ServiceExt;
+
+    #[derive(AcceptExtractor)]
+    enum Accept {
+        #[accept(mediatype = "text/plain")]
+        TextPlain,
+    }
+
+    #[test]
+    fn test_merge_vary() -> Result<(), Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::new();
+        headers.append(header::VARY, "Origin, Accept".parse()?);
+        headers.append(header::VARY, "cookie".parse()?);
+        merge_vary(&mut headers, &[header::ACCEPT, header::ACCEPT_LANGUAGE]);
+        assert_eq!(
+            vec!["Origin, Accept, cookie, accept-language"],
+            headers.get_all(header::VARY).iter().collect::<Vec<_>>()
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::VARY, "*".parse()?);
+        merge_vary(&mut headers, &[header::ACCEPT]);
+        assert_eq!("*", headers[header::VARY]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vary_layer() -> Result<(), Box<dyn std::error::Error>> {
+        async fn negotiated(accept: Accept) -> impl IntoResponse {
+            match accept {
+                Accept::TextPlain => ([(header::VARY, "origin")], "hello world"),
+            }
+        }
+
+        async fn rejected(_accept: Result<Accept, AcceptRejection>) -> StatusCode {
+            StatusCode::NOT_ACCEPTABLE
+        }
+
+        async fn plain() -> &'static str {
+            "hello world"
+        }
+
+        let app = Router::new()
+            .route("/negotiated", get(negotiated))
+            .route("/rejected", get(rejected))
+            .route("/plain", get(plain))
+            .layer(VaryLayer);
+
+        let req = Request::builder()
+            .uri("/negotiated")
+            .header("accept", "text/plain")
+            .body(Body::from(""))?;
+        let response = app.clone().oneshot(req).await?;
+        assert_eq!("origin, accept", response.headers()[header::VARY]);
+
+        let req = Request::builder()
+            .uri("/rejected")
+            .header("accept", "text/csv")
+            .body(Body::from(""))?;
+        let response = app.clone().oneshot(req).await?;
+        assert_eq!("accept", response.headers()[header::VARY]);
+
+        let req = Request::builder().uri("/plain").body(Body::from(""))?;
+        let response = app.oneshot(req).await?;
+        assert!(!response.headers().contains_key(header::VARY));
+        Ok(())
+    }
+}